@@ -0,0 +1,337 @@
+//! Derive macros for newtype wrappers around positions from the `board-game-traits` crate. Enable them through its `derive` feature.
+//!
+//! `DelegatePosition` and `DelegateEvalPosition` implement `Position` and `EvalPosition` for a struct by forwarding every method to one of its fields.
+//!
+//! The inner field is the struct's only field, or the field marked with `#[delegate]`. When the position is created with `start_position_with_settings`, the other fields are created with `Default::default()`.
+//!
+//! Individual methods can be overridden with `#[delegate(method = path)]` on the struct. The generated impl then calls `path` with the same arguments as the trait method, instead of forwarding it. This is typically an inherent method that does some bookkeeping, and forwards to the inner position itself.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use std::collections::HashMap;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Index, Member, Path, Type};
+
+/// Implements `Position` by forwarding to an inner position, including its `MAX_GAME_LENGTH`. See the crate documentation.
+///
+/// # Examples
+/// ```rust
+/// use board_game_traits::{Color, DelegatePosition, GameResult, Position};
+///
+/// #[derive(Clone)]
+/// struct Nim(u32, Color);
+///
+/// impl Position for Nim {
+///     type Move = u32;
+///     type ReverseMove = u32;
+///     type Settings = ();
+///     fn start_position_with_settings(_: &()) -> Self {
+///         Nim(10, Color::White)
+///     }
+///     fn side_to_move(&self) -> Color {
+///         self.1
+///     }
+///     fn generate_moves<E: Extend<u32>>(&self, moves: &mut E) {
+///         moves.extend(1..=self.0.min(3))
+///     }
+///     fn do_move(&mut self, mv: u32) -> u32 {
+///         self.0 -= mv;
+///         self.1 = !self.1;
+///         mv
+///     }
+///     fn reverse_move(&mut self, mv: u32) {
+///         self.0 += mv;
+///         self.1 = !self.1;
+///     }
+///     fn game_result(&self) -> Option<GameResult> {
+///         if self.0 == 0 {
+///             Some(GameResult::win_by(!self.1))
+///         } else {
+///             None
+///         }
+///     }
+/// }
+///
+/// /// Counts the moves played.
+/// #[derive(DelegatePosition)]
+/// #[delegate(do_move = CountingNim::counted_do_move)]
+/// struct CountingNim {
+///     #[delegate]
+///     position: Nim,
+///     moves_played: usize,
+/// }
+///
+/// impl CountingNim {
+///     fn counted_do_move(&mut self, mv: u32) -> u32 {
+///         self.moves_played += 1;
+///         self.position.do_move(mv)
+///     }
+/// }
+///
+/// let mut position = CountingNim::start_position();
+/// position.do_move(3);
+/// position.do_move(2);
+/// assert_eq!(position.moves_played, 2);
+/// assert_eq!(position.side_to_move(), Color::White);
+/// ```
+#[proc_macro_derive(DelegatePosition, attributes(delegate))]
+pub fn derive_delegate_position(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_position(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Implements `EvalPosition` by forwarding to an inner position, including its `BRANCH_FACTOR` and `pruning_profile`. See the crate documentation.
+///
+/// The struct must also implement `Position`, `Clone` and `PartialEq`.
+#[proc_macro_derive(DelegateEvalPosition, attributes(delegate))]
+pub fn derive_delegate_eval_position(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_eval_position(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// The delegated field, and the struct's other fields.
+struct Delegation<'a> {
+    inner: Member,
+    inner_type: &'a Type,
+    other_fields: Vec<Member>,
+    overrides: HashMap<String, Path>,
+}
+
+impl<'a> Delegation<'a> {
+    fn parse(input: &'a DeriveInput) -> syn::Result<Self> {
+        let fields = match &input.data {
+            Data::Struct(data) => &data.fields,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "Delegation can only be derived for structs",
+                ))
+            }
+        };
+        let members: Vec<(Member, &syn::Field)> = fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let member = match &field.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(Index::from(i)),
+                };
+                (member, field)
+            })
+            .collect();
+
+        let marked: Vec<usize> = members
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, field))| {
+                field
+                    .attrs
+                    .iter()
+                    .any(|attr| attr.path().is_ident("delegate"))
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let inner_index = match (marked.as_slice(), members.len()) {
+            ([i], _) => *i,
+            ([], 1) => 0,
+            ([], _) => {
+                return Err(Error::new(
+                    input.span(),
+                    "Mark the field to delegate to with #[delegate]",
+                ))
+            }
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "Only one field can be marked with #[delegate]",
+                ))
+            }
+        };
+
+        let mut overrides = HashMap::new();
+        for attr in input
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("delegate"))
+        {
+            attr.parse_nested_meta(|meta| {
+                let method = match meta.path.get_ident() {
+                    Some(ident) => ident.to_string(),
+                    None => return Err(meta.error("Expected a method name")),
+                };
+                let path: Path = meta.value()?.parse()?;
+                // Both derives read the same attributes, so accept the methods of either trait
+                if is_known_method(&method) {
+                    overrides.insert(method, path);
+                    Ok(())
+                } else {
+                    Err(meta.error(format!("Unknown method `{}`", method)))
+                }
+            })?;
+        }
+
+        Ok(Delegation {
+            inner: members[inner_index].0.clone(),
+            inner_type: &members[inner_index].1.ty,
+            other_fields: members
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != inner_index)
+                .map(|(_, (member, _))| member.clone())
+                .collect(),
+            overrides,
+        })
+    }
+
+    /// Returns the override for the method, called with the given arguments, or the default body.
+    fn body(&self, method: &str, args: TokenStream2, default: TokenStream2) -> TokenStream2 {
+        match self.overrides.get(method) {
+            Some(path) => quote!(#path(#args)),
+            None => default,
+        }
+    }
+}
+
+const POSITION_METHODS: &[&str] = &[
+    "start_position_with_settings",
+    "side_to_move",
+    "generate_moves",
+    "move_is_legal",
+    "do_move",
+    "reverse_move",
+    "game_result",
+];
+
+const EVAL_POSITION_METHODS: &[&str] = &["static_eval", "pruning_profile"];
+
+fn is_known_method(method: &str) -> bool {
+    POSITION_METHODS.contains(&method) || EVAL_POSITION_METHODS.contains(&method)
+}
+
+fn expand_position(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let delegation = Delegation::parse(input)?;
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let inner = &delegation.inner;
+    let inner_type = delegation.inner_type;
+    let position = quote!(::board_game_traits::Position);
+
+    // Braces with field names or indices work for both named and tuple structs
+    let other_fields = &delegation.other_fields;
+    let construct = quote!(#name {
+        #inner: <#inner_type as #position>::start_position_with_settings(settings),
+        #(#other_fields: ::std::default::Default::default(),)*
+    });
+
+    let start_position_with_settings =
+        delegation.body("start_position_with_settings", quote!(settings), construct);
+    let side_to_move = delegation.body(
+        "side_to_move",
+        quote!(self),
+        quote!(#position::side_to_move(&self.#inner)),
+    );
+    let generate_moves = delegation.body(
+        "generate_moves",
+        quote!(self, moves),
+        quote!(#position::generate_moves(&self.#inner, moves)),
+    );
+    let move_is_legal = delegation.body(
+        "move_is_legal",
+        quote!(self, mv),
+        quote!(#position::move_is_legal(&self.#inner, mv)),
+    );
+    let do_move = delegation.body(
+        "do_move",
+        quote!(self, mv),
+        quote!(#position::do_move(&mut self.#inner, mv)),
+    );
+    let reverse_move = delegation.body(
+        "reverse_move",
+        quote!(self, mv),
+        quote!(#position::reverse_move(&mut self.#inner, mv)),
+    );
+    let game_result = delegation.body(
+        "game_result",
+        quote!(self),
+        quote!(#position::game_result(&self.#inner)),
+    );
+    let generic_extend = format_ident!("__E");
+
+    Ok(quote! {
+        impl #impl_generics #position for #name #type_generics #where_clause {
+            type Move = <#inner_type as #position>::Move;
+            type ReverseMove = <#inner_type as #position>::ReverseMove;
+            type Settings = <#inner_type as #position>::Settings;
+
+            fn start_position_with_settings(settings: &Self::Settings) -> Self {
+                #start_position_with_settings
+            }
+
+            fn side_to_move(&self) -> ::board_game_traits::Color {
+                #side_to_move
+            }
+
+            fn generate_moves<#generic_extend: ::std::iter::Extend<Self::Move>>(&self, moves: &mut #generic_extend) {
+                #generate_moves
+            }
+
+            fn move_is_legal(&self, mv: Self::Move) -> bool {
+                #move_is_legal
+            }
+
+            fn do_move(&mut self, mv: Self::Move) -> Self::ReverseMove {
+                #do_move
+            }
+
+            fn reverse_move(&mut self, mv: Self::ReverseMove) {
+                #reverse_move
+            }
+
+            fn game_result(&self) -> ::std::option::Option<::board_game_traits::GameResult> {
+                #game_result
+            }
+
+            const MAX_GAME_LENGTH: ::std::option::Option<u32> = <#inner_type as #position>::MAX_GAME_LENGTH;
+        }
+    })
+}
+
+fn expand_eval_position(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let delegation = Delegation::parse(input)?;
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let inner = &delegation.inner;
+    let inner_type = delegation.inner_type;
+    let eval_position = quote!(::board_game_traits::EvalPosition);
+    let static_eval = delegation.body(
+        "static_eval",
+        quote!(self),
+        quote!(#eval_position::static_eval(&self.#inner)),
+    );
+    let pruning_profile = delegation.body(
+        "pruning_profile",
+        quote!(self),
+        quote!(#eval_position::pruning_profile(&self.#inner)),
+    );
+
+    Ok(quote! {
+        impl #impl_generics #eval_position for #name #type_generics #where_clause {
+            fn static_eval(&self) -> f32 {
+                #static_eval
+            }
+
+            fn pruning_profile(&self) -> ::board_game_traits::PruningHints {
+                #pruning_profile
+            }
+
+            const BRANCH_FACTOR: u64 = <#inner_type as #eval_position>::BRANCH_FACTOR;
+        }
+    })
+}
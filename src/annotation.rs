@@ -0,0 +1,184 @@
+//! A renderer-agnostic model for analysis annotations, such as arrows, highlighted squares and text labels.
+//!
+//! Annotations are generic over the game's square type. They have a compact text form through `Display` and `FromStr`, so that they can be stored in game records and read back by other tools.
+//!
+//! ```rust
+//! use board_game_traits::annotation::{Annotation, AnnotationColor};
+//! let arrow: Annotation<String> = "arrow:red:e2:e4".parse().unwrap();
+//! assert_eq!(arrow.color(), AnnotationColor::Red);
+//! assert_eq!(arrow.to_string(), "arrow:red:e2:e4");
+//! ```
+
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The color of an annotation.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum AnnotationColor {
+    #[default]
+    Green,
+    Red,
+    Yellow,
+    Blue,
+    Rgb(u8, u8, u8),
+}
+
+impl fmt::Display for AnnotationColor {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            AnnotationColor::Green => fmt.write_str("green"),
+            AnnotationColor::Red => fmt.write_str("red"),
+            AnnotationColor::Yellow => fmt.write_str("yellow"),
+            AnnotationColor::Blue => fmt.write_str("blue"),
+            AnnotationColor::Rgb(r, g, b) => write!(fmt, "#{:02x}{:02x}{:02x}", r, g, b),
+        }
+    }
+}
+
+impl FromStr for AnnotationColor {
+    type Err = ParseAnnotationError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "green" => Ok(AnnotationColor::Green),
+            "red" => Ok(AnnotationColor::Red),
+            "yellow" => Ok(AnnotationColor::Yellow),
+            "blue" => Ok(AnnotationColor::Blue),
+            _ => {
+                let hex = input
+                    .strip_prefix('#')
+                    .filter(|hex| hex.len() == 6 && hex.is_ascii())
+                    .ok_or_else(|| ParseAnnotationError::new(input, "invalid color"))?;
+                let component = |i: usize| {
+                    u8::from_str_radix(&hex[i..i + 2], 16)
+                        .map_err(|_| ParseAnnotationError::new(input, "invalid color"))
+                };
+                Ok(AnnotationColor::Rgb(
+                    component(0)?,
+                    component(2)?,
+                    component(4)?,
+                ))
+            }
+        }
+    }
+}
+
+/// A single annotation on the board.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Annotation<Square> {
+    /// An arrow between two squares.
+    Arrow {
+        from: Square,
+        to: Square,
+        color: AnnotationColor,
+    },
+    /// A highlighted square.
+    Highlight {
+        square: Square,
+        color: AnnotationColor,
+    },
+    /// A short text label on a square.
+    Label {
+        square: Square,
+        text: String,
+        color: AnnotationColor,
+    },
+}
+
+impl<Square> Annotation<Square> {
+    /// Returns the color of the annotation.
+    pub fn color(&self) -> AnnotationColor {
+        match *self {
+            Annotation::Arrow { color, .. }
+            | Annotation::Highlight { color, .. }
+            | Annotation::Label { color, .. } => color,
+        }
+    }
+}
+
+/// Writes the annotation in its text form, for example `arrow:green:e2:e4`, `highlight:red:f7` or `label:#ff8000:d5:outpost`.
+/// Squares must not contain `:` in their text form. Label texts may contain any character.
+impl<Square: fmt::Display> fmt::Display for Annotation<Square> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Annotation::Arrow { from, to, color } => write!(fmt, "arrow:{}:{}:{}", color, from, to),
+            Annotation::Highlight { square, color } => {
+                write!(fmt, "highlight:{}:{}", color, square)
+            }
+            Annotation::Label {
+                square,
+                text,
+                color,
+            } => write!(fmt, "label:{}:{}:{}", color, square, text),
+        }
+    }
+}
+
+impl<Square: FromStr> FromStr for Annotation<Square> {
+    type Err = ParseAnnotationError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let parse_square = |s: &str| {
+            s.parse::<Square>()
+                .map_err(|_| ParseAnnotationError::new(input, "invalid square"))
+        };
+        let mut fields = input.splitn(4, ':');
+        let kind = fields.next().unwrap_or_default();
+        let color = fields
+            .next()
+            .ok_or_else(|| ParseAnnotationError::new(input, "missing color"))?
+            .parse()?;
+        let square = parse_square(
+            fields
+                .next()
+                .ok_or_else(|| ParseAnnotationError::new(input, "missing square"))?,
+        )?;
+        let rest = fields.next();
+        match (kind, rest) {
+            ("arrow", Some(to)) => Ok(Annotation::Arrow {
+                from: square,
+                to: parse_square(to)?,
+                color,
+            }),
+            ("highlight", None) => Ok(Annotation::Highlight { square, color }),
+            ("label", Some(text)) => Ok(Annotation::Label {
+                square,
+                text: text.to_string(),
+                color,
+            }),
+            ("arrow", None) | ("label", None) | ("highlight", Some(_)) => {
+                Err(ParseAnnotationError::new(input, "wrong number of fields"))
+            }
+            _ => Err(ParseAnnotationError::new(input, "unknown annotation kind")),
+        }
+    }
+}
+
+/// The error returned when an annotation or annotation color cannot be parsed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseAnnotationError {
+    input: String,
+    reason: &'static str,
+}
+
+impl ParseAnnotationError {
+    fn new(input: &str, reason: &'static str) -> Self {
+        ParseAnnotationError {
+            input: input.to_string(),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for ParseAnnotationError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            fmt,
+            "failed to parse annotation \"{}\": {}",
+            self.input, self.reason
+        )
+    }
+}
+
+impl error::Error for ParseAnnotationError {}
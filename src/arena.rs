@@ -0,0 +1,636 @@
+//! Matches between two engines.
+//!
+//! The engines alternate colors from game to game, and each opening is played once with each engine as white. Clocks are enforced with a `TimeSource`, normally `DefaultTimeSource`, or a `ManualTimeSource` in tests. An engine that runs out of time, or plays an illegal move, loses the game.
+
+use crate::clock::{TimeControl, TimeSource, TimedGame};
+use crate::game::GameEndReason;
+use crate::stats::MatchScore;
+use crate::{
+    ClaimableDraws, Color, Engine, GameResult, PassablePosition, Position, ProgressCounter,
+};
+use std::panic::{self, AssertUnwindSafe};
+
+/// Settings for games in the arena.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArenaOptions {
+    pub time_control: TimeControl,
+    /// Adjudicate the game as a draw after this many plies, including the opening. If None, games are played until they are decided, or reach the game's `MAX_GAME_LENGTH`.
+    pub max_plies: Option<u32>,
+}
+
+/// The record of a single game in the arena.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArenaGame<M> {
+    /// The color played by the first engine in the match.
+    pub first_engine_color: Color,
+    /// The opening moves, which were played before the engines took over.
+    pub opening: Vec<M>,
+    /// The moves played by the engines. If the game ended with an illegal move, that move is not included.
+    pub moves: Vec<M>,
+    pub result: GameResult,
+    pub reason: GameEndReason,
+}
+
+impl<M> ArenaGame<M> {
+    /// Returns the game's result from the perspective of the first engine in the match.
+    pub fn first_engine_score(&self) -> MatchScore {
+        let mut score = MatchScore::default();
+        if self.result == GameResult::Draw {
+            score.draws = 1;
+        } else if self.result == GameResult::win_by(self.first_engine_color) {
+            score.wins = 1;
+        } else {
+            score.losses = 1;
+        }
+        score
+    }
+}
+
+/// Plays a single game between two engines, starting from `start_position` followed by the opening moves.
+///
+/// Panics in `choose_move` are caught, and forfeit the game. This requires the crate to be built with unwinding panics.
+///
+/// The engines' thinking time is measured with `time_source`.
+///
+/// # Panics
+///
+/// Panics if one of the opening moves is illegal, or if the opening ends the game.
+pub fn play_game<P, W, B, T>(
+    white: &mut W,
+    black: &mut B,
+    start_position: &P,
+    opening: &[P::Move],
+    options: &ArenaOptions,
+    time_source: &T,
+) -> ArenaGame<P::Move>
+where
+    P: Position + Clone,
+    W: Engine<P> + ?Sized,
+    B: Engine<P> + ?Sized,
+    T: TimeSource + ?Sized,
+{
+    play_game_adjudicated(
+        white,
+        black,
+        start_position,
+        opening,
+        options,
+        |_, _| None,
+        time_source,
+    )
+}
+
+/// Plays a single game like `play_game`, but claims a draw on behalf of the side to move as soon as one is available. The game then ends with `GameEndReason::DrawClaimed`.
+///
+/// # Panics
+///
+/// Panics if one of the opening moves is illegal, or if the opening ends the game.
+pub fn play_game_claiming_draws<P, W, B, T>(
+    white: &mut W,
+    black: &mut B,
+    start_position: &P,
+    opening: &[P::Move],
+    options: &ArenaOptions,
+    time_source: &T,
+) -> ArenaGame<P::Move>
+where
+    P: ClaimableDraws + Clone,
+    W: Engine<P> + ?Sized,
+    B: Engine<P> + ?Sized,
+    T: TimeSource + ?Sized,
+{
+    play_game_adjudicated(
+        white,
+        black,
+        start_position,
+        opening,
+        options,
+        |position, _| {
+            let claim = position
+                .available_draw_claims()
+                .into_iter()
+                .find(|&claim| position.claim_draw(claim).is_some())?;
+            Some((GameResult::Draw, GameEndReason::DrawClaimed(claim)))
+        },
+        time_source,
+    )
+}
+
+/// Plays a single game like `play_game`, but calls `adjudicate` before every move, once the game is known to be undecided. If it returns a result, the game ends with that result and reason.
+///
+/// `adjudicate` is given the position, and the move that led to it, or None before the first move after the opening.
+///
+/// The engines' thinking time is measured with `time_source`.
+///
+/// # Panics
+///
+/// Panics if one of the opening moves is illegal, or if the opening ends the game.
+pub fn play_game_adjudicated<P, W, B, F, T>(
+    white: &mut W,
+    black: &mut B,
+    start_position: &P,
+    opening: &[P::Move],
+    options: &ArenaOptions,
+    mut adjudicate: F,
+    time_source: &T,
+) -> ArenaGame<P::Move>
+where
+    P: Position + Clone,
+    W: Engine<P> + ?Sized,
+    B: Engine<P> + ?Sized,
+    F: FnMut(&P, Option<&P::Move>) -> Option<(GameResult, GameEndReason)>,
+    T: TimeSource + ?Sized,
+{
+    let mut position = start_position.clone();
+    for mv in opening {
+        assert!(
+            position.move_is_legal(mv.clone()),
+            "Illegal move {:?} in opening",
+            mv
+        );
+        position.do_move(mv.clone());
+    }
+    assert!(
+        position.game_result().is_none(),
+        "The opening must not end the game"
+    );
+
+    white.new_game();
+    black.new_game();
+    let mut game = TimedGame::new(position, options.time_control);
+    let mut moves = vec![];
+    let mut legal_moves = vec![];
+    let mut plies = opening.len() as u32;
+
+    let (result, reason) = loop {
+        let position = game.position();
+        if let Some(result) = position.game_result() {
+            break (result, GameEndReason::Rules);
+        }
+        if let Some(adjudication) = adjudicate(position, moves.last()) {
+            break adjudication;
+        }
+        legal_moves.clear();
+        position.generate_moves(&mut legal_moves);
+        if legal_moves.is_empty() {
+            break (GameResult::Draw, GameEndReason::NoLegalMoves);
+        }
+        if options
+            .max_plies
+            .is_some_and(|max_plies| plies >= max_plies)
+            || P::MAX_GAME_LENGTH.is_some_and(|length| plies >= length)
+        {
+            break (GameResult::Draw, GameEndReason::MaxPlies);
+        }
+
+        let side_to_move = position.side_to_move();
+        let clock = game.time_left(side_to_move);
+        let start_time = time_source.now();
+        let choice = panic::catch_unwind(AssertUnwindSafe(|| match side_to_move {
+            Color::White => white.choose_move(position, clock, options.time_control.increment),
+            Color::Black => black.choose_move(position, clock, options.time_control.increment),
+        }));
+        let mv = match choice {
+            Ok(mv) => mv,
+            Err(_) => break (GameResult::win_by(!side_to_move), GameEndReason::Crash),
+        };
+        let time_used = time_source.now().saturating_sub(start_time);
+        if let Some(outcome) = game.check_flag(time_used) {
+            break (outcome.result, outcome.reason);
+        }
+        if !legal_moves.contains(&mv) {
+            break (
+                GameResult::win_by(!side_to_move),
+                GameEndReason::IllegalMove,
+            );
+        }
+        game.play_move(mv.clone(), time_used);
+        moves.push(mv);
+        plies += 1;
+    };
+
+    ArenaGame {
+        first_engine_color: Color::White,
+        opening: opening.to_vec(),
+        moves,
+        result,
+        reason,
+    }
+}
+
+/// Returns an adjudicator for `play_game_adjudicated`, which declares a draw once `max_plies` plies have been played without an irreversible move.
+///
+/// The count starts from the halfmove clock of the position after the opening. After that, the adjudicator checks every move with `is_irreversible`, keeping a copy of the previous position to do so.
+pub fn no_progress_adjudicator<P: ProgressCounter + Clone>(
+    max_plies: u32,
+) -> impl FnMut(&P, Option<&P::Move>) -> Option<(GameResult, GameEndReason)> {
+    let mut previous: Option<P> = None;
+    let mut plies = 0;
+    move |position, last_move| {
+        plies = match (&previous, last_move) {
+            (Some(previous), Some(mv)) if previous.is_irreversible(mv) => 0,
+            (Some(_), Some(_)) => plies + 1,
+            _ => position.halfmove_clock(),
+        };
+        previous = Some(position.clone());
+        if plies >= max_plies {
+            Some((GameResult::Draw, GameEndReason::NoProgress))
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns an adjudicator for `play_game_adjudicated`, which ends the game once enough consecutive passes have been played, for games whose `game_result` does not detect this itself.
+pub fn passes_adjudicator<P: PassablePosition>(
+) -> impl FnMut(&P, Option<&P::Move>) -> Option<(GameResult, GameEndReason)> {
+    |position, _| {
+        position
+            .passes_result()
+            .map(|result| (result, GameEndReason::ConsecutivePasses))
+    }
+}
+
+/// Plays a match of `num_games` games between two engines.
+///
+/// The first engine plays white in even-numbered games, and black in odd-numbered games. Each pair of games uses the next opening, starting over when all openings have been used. If `openings` is empty, every game is played from `start_position`.
+///
+/// # Panics
+///
+/// Panics if one of the opening moves is illegal, or if an opening ends the game.
+pub fn play_match<P, A, B, T>(
+    first: &mut A,
+    second: &mut B,
+    start_position: &P,
+    openings: &[Vec<P::Move>],
+    num_games: usize,
+    options: &ArenaOptions,
+    time_source: &T,
+) -> Vec<ArenaGame<P::Move>>
+where
+    P: Position + Clone,
+    A: Engine<P> + ?Sized,
+    B: Engine<P> + ?Sized,
+    T: TimeSource + ?Sized,
+{
+    play_match_until(
+        first,
+        second,
+        start_position,
+        openings,
+        num_games,
+        options,
+        |_| false,
+        time_source,
+    )
+}
+
+/// Plays a match like `play_match`, but stops early once `stop` returns true for the games played so far. Plays at most `max_games` games.
+///
+/// Typically used to stop a match once a statistical test has concluded, for example with the closure `|games| sprt.status(match_score(games)) != SprtStatus::Continue`, using `stats::Sprt`.
+///
+/// # Panics
+///
+/// Panics if one of the opening moves is illegal, or if an opening ends the game.
+#[allow(clippy::too_many_arguments)]
+pub fn play_match_until<P, A, B, F, T>(
+    first: &mut A,
+    second: &mut B,
+    start_position: &P,
+    openings: &[Vec<P::Move>],
+    max_games: usize,
+    options: &ArenaOptions,
+    mut stop: F,
+    time_source: &T,
+) -> Vec<ArenaGame<P::Move>>
+where
+    P: Position + Clone,
+    A: Engine<P> + ?Sized,
+    B: Engine<P> + ?Sized,
+    F: FnMut(&[ArenaGame<P::Move>]) -> bool,
+    T: TimeSource + ?Sized,
+{
+    let mut games = vec![];
+    for i in 0..max_games {
+        games.push(play_match_game(
+            first,
+            second,
+            start_position,
+            openings,
+            i,
+            options,
+            time_source,
+        ));
+        if stop(&games) {
+            break;
+        }
+    }
+    games
+}
+
+/// Plays game number `game_index` of a match like `play_match`, with the same opening and colors.
+///
+/// # Panics
+///
+/// Panics if one of the opening moves is illegal, or if the opening ends the game.
+pub fn play_match_game<P, A, B, T>(
+    first: &mut A,
+    second: &mut B,
+    start_position: &P,
+    openings: &[Vec<P::Move>],
+    game_index: usize,
+    options: &ArenaOptions,
+    time_source: &T,
+) -> ArenaGame<P::Move>
+where
+    P: Position + Clone,
+    A: Engine<P> + ?Sized,
+    B: Engine<P> + ?Sized,
+    T: TimeSource + ?Sized,
+{
+    let opening = if openings.is_empty() {
+        &[][..]
+    } else {
+        &openings[(game_index / 2) % openings.len()][..]
+    };
+    if game_index % 2 == 1 {
+        ArenaGame {
+            first_engine_color: Color::Black,
+            ..play_game(second, first, start_position, opening, options, time_source)
+        }
+    } else {
+        play_game(first, second, start_position, opening, options, time_source)
+    }
+}
+
+/// Returns the combined score of a match, from the perspective of the first engine.
+pub fn match_score<M>(games: &[ArenaGame<M>]) -> MatchScore {
+    games
+        .iter()
+        .map(ArenaGame::first_engine_score)
+        .fold(MatchScore::default(), |total, score| MatchScore {
+            wins: total.wins + score.wins,
+            draws: total.draws + score.draws,
+            losses: total.losses + score.losses,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualTimeSource;
+    use crate::testing::{Nim, Walk};
+    use std::time::Duration;
+
+    /// Takes the same number of stones every move, or all of them if there are fewer left.
+    struct Take(u32);
+
+    impl Engine<Nim> for Take {
+        fn choose_move(&mut self, position: &Nim, _: Duration, _: Duration) -> u32 {
+            self.0.min(position.0)
+        }
+    }
+
+    /// Panics on its first move.
+    struct Crashing;
+
+    impl Engine<Nim> for Crashing {
+        fn choose_move(&mut self, _: &Nim, _: Duration, _: Duration) -> u32 {
+            panic!("Crashing engine")
+        }
+    }
+
+    /// Takes one stone, after thinking for `think` on the shared time source.
+    struct Slow<'a> {
+        time_source: &'a ManualTimeSource,
+        think: Duration,
+    }
+
+    impl Engine<Nim> for Slow<'_> {
+        fn choose_move(&mut self, _: &Nim, _: Duration, _: Duration) -> u32 {
+            self.time_source.advance(self.think);
+            1
+        }
+    }
+
+    /// Plays the same move in Walk every move.
+    struct Step(i32);
+
+    impl Engine<Walk> for Step {
+        fn choose_move(&mut self, _: &Walk, _: Duration, _: Duration) -> i32 {
+            self.0
+        }
+    }
+
+    fn options(max_plies: Option<u32>) -> ArenaOptions {
+        ArenaOptions {
+            time_control: TimeControl::new(Duration::from_secs(10), Duration::ZERO),
+            max_plies,
+        }
+    }
+
+    fn play(
+        white: &mut dyn Engine<Nim>,
+        black: &mut dyn Engine<Nim>,
+        max_plies: Option<u32>,
+    ) -> ArenaGame<u32> {
+        play_game(
+            white,
+            black,
+            &Nim::start_position(),
+            &[],
+            &options(max_plies),
+            &ManualTimeSource::default(),
+        )
+    }
+
+    #[test]
+    fn game_is_played_to_the_end() {
+        let game = play(&mut Take(1), &mut Take(3), None);
+        assert_eq!(game.moves, [1, 3, 1, 3, 1, 1]);
+        assert_eq!(
+            (game.result, game.reason),
+            (GameResult::BlackWin, GameEndReason::Rules)
+        );
+    }
+
+    #[test]
+    fn crashing_engine_forfeits() {
+        let game = play(&mut Take(1), &mut Crashing, None);
+        assert_eq!(game.moves, [1]);
+        assert_eq!(
+            (game.result, game.reason),
+            (GameResult::WhiteWin, GameEndReason::Crash)
+        );
+    }
+
+    #[test]
+    fn illegal_move_forfeits() {
+        let game = play(&mut Take(4), &mut Take(1), None);
+        assert!(game.moves.is_empty());
+        assert_eq!(
+            (game.result, game.reason),
+            (GameResult::BlackWin, GameEndReason::IllegalMove)
+        );
+    }
+
+    #[test]
+    fn flag_fall_forfeits() {
+        let time_source = ManualTimeSource::default();
+        let mut white = Slow {
+            time_source: &time_source,
+            think: Duration::from_secs(4),
+        };
+        let mut black = Slow {
+            time_source: &time_source,
+            think: Duration::from_secs(1),
+        };
+        let game = play_game(
+            &mut white,
+            &mut black,
+            &Nim::start_position(),
+            &[],
+            &options(None),
+            &time_source,
+        );
+        // White has used 8 seconds after two moves, and flags on the third
+        assert_eq!(game.moves, [1, 1, 1, 1]);
+        assert_eq!(
+            (game.result, game.reason),
+            (GameResult::BlackWin, GameEndReason::Timeout)
+        );
+        assert_eq!(time_source.now(), Duration::from_secs(14));
+    }
+
+    #[test]
+    fn max_plies_adjudicates_a_draw() {
+        let game = play(&mut Take(1), &mut Take(1), Some(3));
+        assert_eq!(game.moves, [1, 1, 1]);
+        assert_eq!(
+            (game.result, game.reason),
+            (GameResult::Draw, GameEndReason::MaxPlies)
+        );
+    }
+
+    #[test]
+    fn max_plies_counts_the_opening() {
+        let game = play_game(
+            &mut Take(1),
+            &mut Take(1),
+            &Nim::start_position(),
+            &[2, 2],
+            &options(Some(3)),
+            &ManualTimeSource::default(),
+        );
+        assert_eq!(game.moves, [1]);
+        assert_eq!(game.reason, GameEndReason::MaxPlies);
+    }
+
+    #[test]
+    fn adjudicator_ends_the_game() {
+        let game = play_game_adjudicated(
+            &mut Take(1),
+            &mut Take(1),
+            &Nim::start_position(),
+            &[],
+            &options(None),
+            |position: &Nim, _: Option<&u32>| {
+                if position.0 <= 5 {
+                    Some((GameResult::Draw, GameEndReason::NoProgress))
+                } else {
+                    None
+                }
+            },
+            &ManualTimeSource::default(),
+        );
+        assert_eq!(game.moves.len(), 5);
+        assert_eq!(game.reason, GameEndReason::NoProgress);
+    }
+
+    #[test]
+    fn no_progress_adjudicator_counts_reversible_moves() {
+        let play_walk = |white: i32, opening: &[i32], max_plies| {
+            play_game_adjudicated(
+                &mut Step(white),
+                &mut Step(-1),
+                &Walk::start_position(),
+                opening,
+                &options(max_plies),
+                no_progress_adjudicator(4),
+                &ManualTimeSource::default(),
+            )
+        };
+        let game = play_walk(1, &[], None);
+        assert_eq!(game.moves, [1, -1, 1, -1]);
+        assert_eq!(game.reason, GameEndReason::NoProgress);
+
+        // The count starts from the halfmove clock after the opening
+        let game = play_walk(1, &[1, -1, 1], None);
+        assert_eq!(game.moves, [-1]);
+        assert_eq!(game.reason, GameEndReason::NoProgress);
+
+        // Every second move is irreversible
+        let game = play_walk(0, &[], Some(10));
+        assert_eq!(game.moves.len(), 10);
+        assert_eq!(game.reason, GameEndReason::MaxPlies);
+    }
+
+    #[test]
+    fn match_games_alternate_colors() {
+        let openings = vec![vec![1], vec![2]];
+        let time_source = ManualTimeSource::default();
+        let games: Vec<ArenaGame<u32>> = (0..4)
+            .map(|i| {
+                play_match_game(
+                    &mut Take(1),
+                    &mut Take(3),
+                    &Nim::start_position(),
+                    &openings,
+                    i,
+                    &options(None),
+                    &time_source,
+                )
+            })
+            .collect();
+        let colors: Vec<Color> = games.iter().map(|game| game.first_engine_color).collect();
+        assert_eq!(
+            colors,
+            [Color::White, Color::Black, Color::White, Color::Black]
+        );
+        let played_openings: Vec<&[u32]> = games.iter().map(|game| &game.opening[..]).collect();
+        assert_eq!(played_openings, [&[1][..], &[1], &[2], &[2]]);
+        // After the one-stone opening, black moves first, so the first move comes from the engine playing black
+        assert_eq!(games[0].moves[0], 3);
+        assert_eq!(games[1].moves[0], 1);
+
+        let games = play_match(
+            &mut Take(1),
+            &mut Take(3),
+            &Nim::start_position(),
+            &openings,
+            4,
+            &options(None),
+            &time_source,
+        );
+        assert_eq!(
+            games
+                .iter()
+                .map(|game| game.first_engine_color)
+                .collect::<Vec<_>>(),
+            colors
+        );
+        // Both openings decide the game for one color, whichever engine plays it, so alternating colors evens the score
+        let results: Vec<GameResult> = games.iter().map(|game| game.result).collect();
+        assert_eq!(
+            results,
+            [
+                GameResult::BlackWin,
+                GameResult::BlackWin,
+                GameResult::WhiteWin,
+                GameResult::WhiteWin
+            ]
+        );
+        let score = match_score(&games);
+        assert_eq!((score.wins, score.draws, score.losses), (2, 0, 2));
+    }
+}
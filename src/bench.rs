@@ -0,0 +1,130 @@
+//! A standardized benchmark, for tracking the performance of move generation and of the crate's search.
+//!
+//! The workload is a fixed set of positions: the start position, followed by positions reached by random play from a seeded generator. The same seed and settings always give the same positions, so node counts can be compared between runs, and only the time should change.
+//!
+//! The crate has no fixed-depth search, so the search workload runs the proof-number solver with a node limit instead.
+
+use crate::clock::{DefaultTimeSource, TimeSource};
+use crate::game::random_game;
+use crate::{perft, solver, Position};
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use std::fmt;
+use std::time::Duration;
+
+/// Settings for a benchmark run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BenchOptions {
+    /// The number of positions reached by random play, in addition to the start position.
+    pub random_positions: usize,
+    /// The maximum number of random moves played to reach each position.
+    pub max_plies: u32,
+    /// The seed for the random play.
+    pub seed: u64,
+    /// The perft depth from each position.
+    pub perft_depth: u16,
+    /// The node limit for each search with the solver.
+    pub solver_nodes: usize,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        BenchOptions {
+            random_positions: 10,
+            max_plies: 10,
+            seed: 0,
+            perft_depth: 3,
+            solver_nodes: 10_000,
+        }
+    }
+}
+
+/// The nodes visited and time taken by a benchmark workload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct BenchResult {
+    pub nodes: u64,
+    pub time: Duration,
+}
+
+impl BenchResult {
+    /// Returns the number of nodes per second, or 0 if no time was measured.
+    pub fn nps(&self) -> u64 {
+        let seconds = self.time.as_secs_f64();
+        if seconds == 0.0 {
+            0
+        } else {
+            (self.nodes as f64 / seconds) as u64
+        }
+    }
+}
+
+impl fmt::Display for BenchResult {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            fmt,
+            "{} nodes in {:.3}s, {} nps",
+            self.nodes,
+            self.time.as_secs_f64(),
+            self.nps()
+        )
+    }
+}
+
+/// Returns the benchmark's positions: the start position, followed by `options.random_positions` positions reached by random play.
+pub fn bench_positions<P: Position + Clone>(
+    settings: &P::Settings,
+    options: &BenchOptions,
+) -> Vec<P> {
+    let mut rng = SmallRng::seed_from_u64(options.seed);
+    let mut positions = vec![P::start_position_with_settings(settings)];
+    positions.extend(
+        (0..options.random_positions)
+            .map(|_| random_game::<P, _>(settings, &mut rng, options.max_plies).final_position()),
+    );
+    positions
+}
+
+/// Runs perft to `options.perft_depth` from every benchmark position.
+/// # Examples
+/// ```rust
+/// use board_game_traits::bench::{bench_perft, BenchOptions};
+/// # use board_game_traits::{Color, GameResult, Position};
+/// # use board_game_traits::testing::Nim;
+/// let options = BenchOptions { random_positions: 0, perft_depth: 2, ..BenchOptions::default() };
+/// let result = bench_perft::<Nim>(&(), &options);
+/// assert_eq!(result.nodes, 9);
+/// println!("{}", result);
+/// ```
+pub fn bench_perft<P: Position + Clone>(
+    settings: &P::Settings,
+    options: &BenchOptions,
+) -> BenchResult {
+    let mut positions = bench_positions::<P>(settings, options);
+    let time_source = DefaultTimeSource::default();
+    let nodes = positions
+        .iter_mut()
+        .map(|position| perft::perft(position, options.perft_depth))
+        .sum();
+    BenchResult {
+        nodes,
+        time: time_source.now(),
+    }
+}
+
+/// Runs the proof-number solver with a limit of `options.solver_nodes` nodes from every undecided benchmark position.
+pub fn bench_solver<P: Position + Clone>(
+    settings: &P::Settings,
+    options: &BenchOptions,
+) -> BenchResult {
+    let mut positions = bench_positions::<P>(settings, options);
+    let time_source = DefaultTimeSource::default();
+    let nodes = positions
+        .iter_mut()
+        .filter(|position| position.game_result().is_none())
+        .map(|position| solver::solve(position, options.solver_nodes).nodes as u64)
+        .sum();
+    BenchResult {
+        nodes,
+        time: time_source.now(),
+    }
+}
@@ -0,0 +1,489 @@
+//! Game clocks: time controls, clocks attached to a game, and utilities for displaying them.
+//!
+//! All formatting rounds down, so a clock never shows more time than is actually left.
+
+use crate::game::{GameEndReason, GameOutcome};
+use crate::{Color, GameResult, Position};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Below this much remaining time, clocks are displayed with tenths of a second.
+pub const TENTHS_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// A time control with a base time, a per-move increment (Fischer time) and a per-move delay.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeControl {
+    /// Each side's time at the start of the game.
+    pub time: Duration,
+    /// The time added to a side's clock after each of its moves.
+    pub increment: Duration,
+    /// The time at the start of each move that is not deducted from the clock (simple delay).
+    pub delay: Duration,
+}
+
+impl TimeControl {
+    /// Returns a time control with the given base time and increment, and no delay.
+    pub fn new(time: Duration, increment: Duration) -> Self {
+        TimeControl {
+            time,
+            increment,
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Returns a time control with the given base time and delay, and no increment.
+    pub fn with_delay(time: Duration, delay: Duration) -> Self {
+        TimeControl {
+            time,
+            increment: Duration::ZERO,
+            delay,
+        }
+    }
+}
+
+/// A position together with both players' clocks.
+///
+/// The time used for each move is given by the caller, so that games can be timed by a wall clock, or replayed with recorded times.
+/// # Examples
+/// ```rust
+/// use board_game_traits::game::GameEndReason;
+/// use board_game_traits::clock::{TimeControl, TimedGame};
+/// use board_game_traits::{Color, GameResult, Position};
+/// use std::time::Duration;
+/// # use board_game_traits::testing::Nim;
+/// let time_control = TimeControl::new(Duration::from_secs(10), Duration::from_secs(1));
+/// let mut game = TimedGame::new(Nim::start_position(), time_control);
+/// assert_eq!(game.play_move(3, Duration::from_secs(4)), None);
+/// assert_eq!(game.time_left(Color::White), Duration::from_secs(7));
+///
+/// let outcome = game.play_move(3, Duration::from_secs(11)).unwrap();
+/// assert_eq!(outcome.result, GameResult::WhiteWin);
+/// assert_eq!(outcome.reason, GameEndReason::Timeout);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TimedGame<P> {
+    position: P,
+    time_control: TimeControl,
+    time_left: [Duration; 2],
+    outcome: Option<GameOutcome>,
+}
+
+impl<P: Position> TimedGame<P> {
+    /// Starts both clocks from the time control's base time.
+    pub fn new(position: P, time_control: TimeControl) -> Self {
+        let outcome = position.game_result().map(|result| GameOutcome {
+            result,
+            reason: GameEndReason::Rules,
+        });
+        TimedGame {
+            position,
+            time_control,
+            time_left: [time_control.time; 2],
+            outcome,
+        }
+    }
+
+    pub fn position(&self) -> &P {
+        &self.position
+    }
+
+    pub fn into_position(self) -> P {
+        self.position
+    }
+
+    pub fn time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    /// Returns the time left on the side's clock, as of the start of the current move.
+    pub fn time_left(&self, color: Color) -> Duration {
+        self.time_left[color.disc()]
+    }
+
+    /// Returns how the game ended, or None if it is still being played.
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        self.outcome
+    }
+
+    /// Checks whether the side to move has run out of time after thinking for `time_used` on the current move, for example while waiting for its move. If so, it loses the game on time.
+    ///
+    /// Returns the outcome if the game has ended.
+    pub fn check_flag(&mut self, time_used: Duration) -> Option<GameOutcome> {
+        if self.outcome.is_none() {
+            let side_to_move = self.position.side_to_move();
+            if time_used.saturating_sub(self.time_control.delay) > self.time_left(side_to_move) {
+                self.outcome = Some(GameOutcome {
+                    result: GameResult::win_by(!side_to_move),
+                    reason: GameEndReason::Timeout,
+                });
+            }
+        }
+        self.outcome
+    }
+
+    /// Plays a legal move that took `time_used` to choose, and updates the mover's clock.
+    ///
+    /// If the side to move ran out of time, the move is not played, and the side loses on time. Returns the outcome if the game has ended.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the game has already ended.
+    pub fn play_move(&mut self, mv: P::Move, time_used: Duration) -> Option<GameOutcome> {
+        assert!(
+            self.outcome.is_none(),
+            "Cannot play a move after the game has ended"
+        );
+        if self.check_flag(time_used).is_some() {
+            return self.outcome;
+        }
+        let clock = &mut self.time_left[self.position.side_to_move().disc()];
+        *clock = *clock - time_used.saturating_sub(self.time_control.delay)
+            + self.time_control.increment;
+        self.position.do_move(mv);
+        self.outcome = self.position.game_result().map(|result| GameOutcome {
+            result,
+            reason: GameEndReason::Rules,
+        });
+        self.outcome
+    }
+}
+
+/// Formats the remaining time as `m:ss`, or `h:mm:ss` if it is at least one hour.
+/// Below ten seconds, tenths of a second are included, as in `0:09.4`.
+/// # Examples
+/// ```rust
+/// use board_game_traits::clock::format_clock;
+/// use std::time::Duration;
+/// assert_eq!(format_clock(Duration::from_secs(3725)), "1:02:05");
+/// assert_eq!(format_clock(Duration::from_millis(65_999)), "1:05");
+/// assert_eq!(format_clock(Duration::from_millis(9_470)), "0:09.4");
+/// ```
+pub fn format_clock(time: Duration) -> String {
+    let total_seconds = time.as_secs();
+    let hours = total_seconds / 3600;
+    let minutes = total_seconds / 60 % 60;
+    let seconds = total_seconds % 60;
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else if time < TENTHS_THRESHOLD {
+        format!("{}:{:02}.{}", minutes, seconds, time.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Formats a byo-yomi clock. While main time remains, the periods are shown after it, as in `5:00 (3 × 0:30)`.
+/// Once the main time has run out, the time left in the current period is shown, followed by the number of periods left, as in `0:27 (2)`.
+/// # Examples
+/// ```rust
+/// use board_game_traits::clock::format_byo_yomi;
+/// use std::time::Duration;
+/// let period = Duration::from_secs(30);
+/// assert_eq!(format_byo_yomi(Duration::from_secs(300), 3, period, period), "5:00 (3 × 0:30)");
+/// assert_eq!(format_byo_yomi(Duration::ZERO, 2, Duration::from_secs(27), period), "0:27 (2)");
+/// ```
+pub fn format_byo_yomi(
+    main_time: Duration,
+    periods_left: u32,
+    period_time_left: Duration,
+    period_time: Duration,
+) -> String {
+    if main_time > Duration::ZERO {
+        format!(
+            "{} ({} × {})",
+            format_clock(main_time),
+            periods_left,
+            format_clock(period_time)
+        )
+    } else {
+        format!("{} ({})", format_clock(period_time_left), periods_left)
+    }
+}
+
+/// A source of the current time, for measuring how long moves take and for driving clock displays.
+///
+/// All timing code in the crate goes through a time source, so that it can be mocked in tests with `ManualTimeSource`, and run on platforms without `std::time::Instant`.
+pub trait TimeSource {
+    /// Returns the time elapsed since a fixed starting point, such as the creation of the time source. Never decreases.
+    fn now(&self) -> Duration;
+
+    /// Blocks until `duration` has passed.
+    fn sleep(&self, duration: Duration);
+}
+
+/// The time source used by default: `JsTimeSource` on `wasm32` with the `wasm` feature, and `SystemTimeSource` everywhere else.
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+pub type DefaultTimeSource = SystemTimeSource;
+
+/// The time source used by default: `JsTimeSource` on `wasm32` with the `wasm` feature, and `SystemTimeSource` everywhere else.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub type DefaultTimeSource = JsTimeSource;
+
+/// A time source based on `std::time::Instant`. Panics on `wasm32-unknown-unknown`, where `Instant` is not available.
+#[derive(Clone, Copy, Debug)]
+pub struct SystemTimeSource {
+    start: Instant,
+}
+
+impl Default for SystemTimeSource {
+    fn default() -> Self {
+        SystemTimeSource {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl TimeSource for SystemTimeSource {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+/// A time source for `wasm32-unknown-unknown`, based on JavaScript's `Date.now()`, with millisecond precision. Available with the `wasm` feature.
+///
+/// The browser's main thread cannot block, so `sleep` busy-waits.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug)]
+pub struct JsTimeSource {
+    start_millis: f64,
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+impl Default for JsTimeSource {
+    fn default() -> Self {
+        JsTimeSource {
+            start_millis: js_sys::Date::now(),
+        }
+    }
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+impl TimeSource for JsTimeSource {
+    fn now(&self) -> Duration {
+        Duration::from_secs_f64((js_sys::Date::now() - self.start_millis).max(0.0) / 1000.0)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        let end = self.now() + duration;
+        while self.now() < end {}
+    }
+}
+
+/// A time source that only moves when told to, for deterministic tests.
+///
+/// `sleep` advances the time instantly. The time can be shared between threads, for example by an engine that advances it to simulate thinking.
+/// # Examples
+/// ```rust
+/// use board_game_traits::clock::{ManualTimeSource, TimeSource};
+/// use std::time::Duration;
+/// let time_source = ManualTimeSource::default();
+/// time_source.advance(Duration::from_millis(1500));
+/// time_source.sleep(Duration::from_secs(1));
+/// assert_eq!(time_source.now(), Duration::from_millis(2500));
+/// ```
+#[derive(Debug, Default)]
+pub struct ManualTimeSource {
+    nanos: AtomicU64,
+}
+
+impl ManualTimeSource {
+    /// Moves the time forward.
+    pub fn advance(&self, duration: Duration) {
+        self.nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+impl TimeSource for ManualTimeSource {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration)
+    }
+}
+
+impl<T: TimeSource + ?Sized> TimeSource for &T {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        (**self).sleep(duration)
+    }
+}
+
+/// A clock counting down in real time, for driving clock displays.
+///
+/// The countdown never blocks. The caller polls it from its own event loop, timer or animation frame, and can use `until_next_tick` to schedule the next poll.
+/// Ticks fall on whole multiples of the tick length, so that the displayed value changes exactly on time.
+#[derive(Debug)]
+pub struct Countdown<T = DefaultTimeSource> {
+    time_source: T,
+    deadline: Duration,
+    tick: Option<Duration>,
+    shown: Option<Duration>,
+}
+
+impl Countdown {
+    /// Starts a countdown from `time_left`, ticking at a fixed interval.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tick` is zero.
+    pub fn new(time_left: Duration, tick: Duration) -> Self {
+        Countdown::with_time_source(DefaultTimeSource::default(), time_left, Some(tick))
+    }
+
+    /// Starts a countdown that ticks every second, and every tenth of a second below `TENTHS_THRESHOLD`, matching `format_clock`.
+    pub fn for_display(time_left: Duration) -> Self {
+        Countdown::with_time_source(DefaultTimeSource::default(), time_left, None)
+    }
+}
+
+impl<T: TimeSource> Countdown<T> {
+    /// Starts a countdown from `time_left`, using the given time source. Ticks at a fixed interval, or like `for_display` if `tick` is None.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tick` is zero.
+    /// # Examples
+    /// ```rust
+    /// use board_game_traits::clock::{Countdown, ManualTimeSource};
+    /// use std::time::Duration;
+    /// let time_source = ManualTimeSource::default();
+    /// let mut countdown = Countdown::with_time_source(
+    ///     &time_source,
+    ///     Duration::from_millis(2500),
+    ///     Some(Duration::from_secs(1)),
+    /// );
+    /// assert_eq!(countdown.poll(), Some(Duration::from_millis(2500)));
+    /// assert_eq!(countdown.poll(), None);
+    /// assert_eq!(countdown.until_next_tick(), Some(Duration::from_millis(500)));
+    ///
+    /// time_source.advance(Duration::from_millis(500));
+    /// assert_eq!(countdown.poll(), Some(Duration::from_secs(2)));
+    ///
+    /// // Ticks that were missed between polls are skipped
+    /// time_source.advance(Duration::from_millis(1700));
+    /// assert_eq!(countdown.poll(), Some(Duration::from_secs(1)));
+    ///
+    /// time_source.advance(Duration::from_secs(1));
+    /// assert_eq!(countdown.poll(), Some(Duration::ZERO));
+    /// assert!(countdown.is_finished());
+    /// assert_eq!(countdown.poll(), None);
+    /// ```
+    pub fn with_time_source(time_source: T, time_left: Duration, tick: Option<Duration>) -> Self {
+        assert!(
+            tick != Some(Duration::ZERO),
+            "Countdown tick must be positive"
+        );
+        Countdown {
+            deadline: time_source.now() + time_left,
+            time_source,
+            tick,
+            shown: None,
+        }
+    }
+
+    /// Returns the time left to display, if it has changed since the last poll.
+    ///
+    /// The first poll returns the exact time left. Later polls return the most recent tick that has passed, or None if no tick has passed since the last poll. The last value is always `Duration::ZERO`.
+    pub fn poll(&mut self) -> Option<Duration> {
+        if self.is_finished() {
+            return None;
+        }
+        let time_left = self.time_left();
+        let shown = match self.shown {
+            None => time_left,
+            Some(shown) => {
+                // Round up to the tick that was last passed, so that rounding down never skips a displayed value
+                let tick_length = self.tick_length(time_left).as_nanos();
+                let ticks = time_left.as_nanos().div_ceil(tick_length);
+                let tick = Duration::from_nanos((ticks * tick_length) as u64);
+                if tick >= shown {
+                    return None;
+                }
+                tick
+            }
+        };
+        self.shown = Some(shown);
+        Some(shown)
+    }
+
+    /// Returns how long until the next poll will return a new value, or None if the countdown is finished.
+    pub fn until_next_tick(&self) -> Option<Duration> {
+        match self.shown {
+            _ if self.is_finished() => None,
+            None => Some(Duration::ZERO),
+            Some(shown) => {
+                let tick_length = self.tick_length(shown);
+                let mut past_tick =
+                    Duration::from_nanos((shown.as_nanos() % tick_length.as_nanos()) as u64);
+                if past_tick == Duration::ZERO {
+                    past_tick = tick_length;
+                }
+                Some(self.time_left().saturating_sub(shown - past_tick))
+            }
+        }
+    }
+
+    /// Returns true once `Duration::ZERO` has been returned by `poll`.
+    pub fn is_finished(&self) -> bool {
+        self.shown == Some(Duration::ZERO)
+    }
+
+    fn time_left(&self) -> Duration {
+        self.deadline.saturating_sub(self.time_source.now())
+    }
+
+    fn tick_length(&self, time_left: Duration) -> Duration {
+        self.tick.unwrap_or(if time_left > TENTHS_THRESHOLD {
+            Duration::from_secs(1)
+        } else {
+            Duration::from_millis(100)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn countdown_for_display_switches_to_tenths() {
+        let time_source = ManualTimeSource::default();
+        let mut countdown =
+            Countdown::with_time_source(&time_source, Duration::from_millis(10_500), None);
+        assert_eq!(countdown.poll(), Some(Duration::from_millis(10_500)));
+        assert_eq!(
+            countdown.until_next_tick(),
+            Some(Duration::from_millis(500))
+        );
+
+        time_source.advance(Duration::from_millis(500));
+        assert_eq!(countdown.poll(), Some(Duration::from_secs(10)));
+        assert_eq!(
+            countdown.until_next_tick(),
+            Some(Duration::from_millis(100))
+        );
+
+        time_source.advance(Duration::from_millis(50));
+        assert_eq!(countdown.poll(), None);
+        assert_eq!(countdown.until_next_tick(), Some(Duration::from_millis(50)));
+
+        time_source.advance(Duration::from_millis(50));
+        assert_eq!(countdown.poll(), Some(Duration::from_millis(9_900)));
+
+        time_source.advance(Duration::from_secs(20));
+        assert_eq!(countdown.poll(), Some(Duration::ZERO));
+        assert_eq!(countdown.until_next_tick(), None);
+    }
+}
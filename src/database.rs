@@ -0,0 +1,331 @@
+//! A compact binary format for large collections of games, such as self-play games for training.
+//!
+//! Every game is played from the same start position, which is not stored. Moves are stored through `CompressedMove`, as variable-length integers, so most moves take one or two bytes.
+//! Each game starts with a header holding its result, length and tags. Readers can filter games by their header, and skip the moves of games that do not match without decoding them.
+//!
+//! The file starts with the magic bytes `BGDB` and a version byte, followed by the games. Each game is stored as:
+//! * The result: 0 if undecided, 1 for a white win, 2 for a black win, and 3 for a draw.
+//! * The draw claim that ended the game: 0 if none, 1 for repetition, 2 for a move counter, and 3 for other claims.
+//! * The number of tags, followed by each tag's key and value, as length-prefixed UTF-8 strings.
+//! * The number of plies.
+//! * The length of the moves in bytes, followed by the moves.
+//!
+//! All numbers are unsigned LEB128 variable-length integers.
+//!
+//! The format has no block compression and no index. Moves are already compact, and whole files compress well with general-purpose compressors, which can be layered on top through the `Read` and `Write` streams.
+//! Filtering by result, length or tags is a sequential scan over the headers, which skips the moves of games that do not match.
+
+use crate::game::Game;
+use crate::{CompressedMove, DrawClaim, GameResult, Position};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 4] = b"BGDB";
+const VERSION: u8 = 1;
+
+/// The header of a game in the database, which can be read without decoding the moves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameHeader {
+    pub result: Option<GameResult>,
+    /// The draw claim that ended the game, if any.
+    pub draw_claim: Option<DrawClaim>,
+    /// The number of moves in the game.
+    pub plies: usize,
+    /// Key-value pairs describing the game, such as the players or the event.
+    pub tags: Vec<(String, String)>,
+}
+
+impl GameHeader {
+    /// Returns the value of the first tag with the given key, if any.
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_key, _)| tag_key == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Writes games to a database, one at a time.
+/// # Examples
+/// ```rust
+/// use board_game_traits::database::{DatabaseReader, DatabaseWriter};
+/// use board_game_traits::game::Game;
+/// use board_game_traits::{DrawClaim, GameResult, Position};
+/// # use board_game_traits::{Color, CompressedMove};
+/// # use board_game_traits::testing::Nim;
+/// let short_game = Game {
+///     start_position: Nim::start_position(),
+///     moves: vec![3, 3, 3, 1],
+///     result: Some(GameResult::BlackWin),
+///     draw_claim: None,
+/// };
+/// let unfinished_game = Game {
+///     start_position: Nim::start_position(),
+///     moves: vec![1, 2],
+///     result: None,
+///     draw_claim: None,
+/// };
+/// let claimed_draw = Game {
+///     start_position: Nim::start_position(),
+///     moves: vec![2],
+///     result: Some(GameResult::Draw),
+///     draw_claim: Some(DrawClaim::Other),
+/// };
+///
+/// let mut writer = DatabaseWriter::new(vec![]).unwrap();
+/// writer.write_game(&short_game, &[("white".to_string(), "alice".to_string())]).unwrap();
+/// writer.write_game(&unfinished_game, &[]).unwrap();
+/// writer.write_game(&claimed_draw, &[]).unwrap();
+/// let bytes = writer.finish().unwrap();
+///
+/// let reader = DatabaseReader::<_, Nim>::new(&bytes[..], ()).unwrap();
+/// let decided: Vec<_> = reader
+///     .games_matching(|header| header.result.is_some())
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(decided.len(), 2);
+/// assert_eq!(decided[0].0.tag("white"), Some("alice"));
+/// assert_eq!(decided[0].1, short_game);
+/// assert_eq!(decided[1].1, claimed_draw);
+/// ```
+#[derive(Debug)]
+pub struct DatabaseWriter<W> {
+    writer: W,
+    moves: Vec<u8>,
+}
+
+impl<W: Write> DatabaseWriter<W> {
+    /// Starts a new database, and writes its header.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(DatabaseWriter {
+            writer,
+            moves: vec![],
+        })
+    }
+
+    /// Appends a game, with the given tags. The game must start from the start position the database will be read with.
+    pub fn write_game<P: CompressedMove>(
+        &mut self,
+        game: &Game<P>,
+        tags: &[(String, String)],
+    ) -> io::Result<()> {
+        let result = match game.result {
+            None => 0,
+            Some(GameResult::WhiteWin) => 1,
+            Some(GameResult::BlackWin) => 2,
+            Some(GameResult::Draw) => 3,
+        };
+        let draw_claim = match game.draw_claim {
+            None => 0,
+            Some(DrawClaim::Repetition) => 1,
+            Some(DrawClaim::MoveCounter) => 2,
+            Some(DrawClaim::Other) => 3,
+        };
+        self.writer.write_all(&[result, draw_claim])?;
+        write_varint(&mut self.writer, tags.len() as u64)?;
+        for (key, value) in tags {
+            write_string(&mut self.writer, key)?;
+            write_string(&mut self.writer, value)?;
+        }
+        write_varint(&mut self.writer, game.moves.len() as u64)?;
+
+        self.moves.clear();
+        for mv in game.moves.iter() {
+            write_varint(&mut self.moves, P::compress(mv) as u64)?;
+        }
+        write_varint(&mut self.writer, self.moves.len() as u64)?;
+        self.writer.write_all(&self.moves)
+    }
+
+    /// Flushes the database, and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads games from a database, one at a time.
+#[derive(Debug)]
+pub struct DatabaseReader<R, P: Position> {
+    reader: R,
+    start_position: P,
+}
+
+impl<R: Read, P: CompressedMove + Clone> DatabaseReader<R, P> {
+    /// Opens a database whose games start from the start position with the given settings, and checks its header.
+    pub fn new(mut reader: R, settings: P::Settings) -> io::Result<Self> {
+        let mut header = [0; 5];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid_data("Not a game database"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data("Unsupported game database version"));
+        }
+        Ok(DatabaseReader {
+            reader,
+            start_position: P::start_position_with_settings(&settings),
+        })
+    }
+
+    /// Reads the next game's header, or returns None at the end of the database.
+    /// Must be followed by either `read_moves` or `skip_moves`.
+    pub fn read_header(&mut self) -> io::Result<Option<GameHeader>> {
+        let mut result = [0];
+        if self.reader.read(&mut result)? == 0 {
+            return Ok(None);
+        }
+        let result = match result[0] {
+            0 => None,
+            1 => Some(GameResult::WhiteWin),
+            2 => Some(GameResult::BlackWin),
+            3 => Some(GameResult::Draw),
+            _ => return Err(invalid_data("Invalid game result")),
+        };
+        let mut draw_claim = [0];
+        self.reader.read_exact(&mut draw_claim)?;
+        let draw_claim = match draw_claim[0] {
+            0 => None,
+            1 => Some(DrawClaim::Repetition),
+            2 => Some(DrawClaim::MoveCounter),
+            3 => Some(DrawClaim::Other),
+            _ => return Err(invalid_data("Invalid draw claim")),
+        };
+        let num_tags = read_varint(&mut self.reader)?;
+        let tags = (0..num_tags)
+            .map(|_| {
+                Ok((
+                    read_string(&mut self.reader)?,
+                    read_string(&mut self.reader)?,
+                ))
+            })
+            .collect::<io::Result<_>>()?;
+        let plies = read_varint(&mut self.reader)? as usize;
+        Ok(Some(GameHeader {
+            result,
+            draw_claim,
+            plies,
+            tags,
+        }))
+    }
+
+    /// Reads the moves of the game whose header was just read, and checks that they are legal.
+    pub fn read_moves(&mut self, header: &GameHeader) -> io::Result<Game<P>> {
+        let length = read_varint(&mut self.reader)?;
+        let mut bytes = vec![];
+        (&mut self.reader).take(length).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let mut position = self.start_position.clone();
+        let mut bytes = &bytes[..];
+        let mut moves = Vec::with_capacity(header.plies.min(bytes.len()));
+        for _ in 0..header.plies {
+            let compressed = read_varint(&mut bytes)?;
+            let mv = u32::try_from(compressed)
+                .ok()
+                .and_then(|compressed| position.decompress(compressed))
+                .ok_or_else(|| invalid_data("Illegal move in game"))?;
+            position.do_move(mv.clone());
+            moves.push(mv);
+        }
+        Ok(Game {
+            start_position: self.start_position.clone(),
+            moves,
+            result: header.result,
+            draw_claim: header.draw_claim,
+        })
+    }
+
+    /// Skips the moves of the game whose header was just read, without decoding them.
+    pub fn skip_moves(&mut self) -> io::Result<()> {
+        let length = read_varint(&mut self.reader)?;
+        let skipped = io::copy(&mut (&mut self.reader).take(length), &mut io::sink())?;
+        if skipped != length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+
+    /// Returns an iterator over every game in the database, with its header.
+    pub fn games(self) -> impl Iterator<Item = io::Result<(GameHeader, Game<P>)>> {
+        self.games_matching(|_| true)
+    }
+
+    /// Returns an iterator over the games whose header matches the filter. The moves of other games are skipped without decoding them.
+    /// The iterator stops after the first error.
+    pub fn games_matching<F>(
+        mut self,
+        mut filter: F,
+    ) -> impl Iterator<Item = io::Result<(GameHeader, Game<P>)>>
+    where
+        F: FnMut(&GameHeader) -> bool,
+    {
+        let mut failed = false;
+        std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            let game = loop {
+                let header = match self.read_header() {
+                    Ok(Some(header)) => header,
+                    Ok(None) => return None,
+                    Err(err) => break Err(err),
+                };
+                if filter(&header) {
+                    break self.read_moves(&header).map(|game| (header, game));
+                } else if let Err(err) = self.skip_moves() {
+                    break Err(err);
+                }
+            };
+            failed = game.is_err();
+            Some(game)
+        })
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return writer.write_all(&[byte]);
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("Integer is too large"))
+}
+
+fn write_string<W: Write>(writer: &mut W, string: &str) -> io::Result<()> {
+    write_varint(writer, string.len() as u64)?;
+    writer.write_all(string.as_bytes())
+}
+
+fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    let length = read_varint(reader)?;
+    let mut bytes = vec![];
+    reader.take(length).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(bytes).map_err(|_| invalid_data("Tag is not valid UTF-8"))
+}
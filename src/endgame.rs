@@ -0,0 +1,92 @@
+//! Plug-in endgame knowledge, for positions where the static evaluation is known to be wrong.
+//!
+//! Recognizers are registered for specific material configurations, and either replace the static evaluation with an exact score (e.g. a bare minor piece cannot win), or scale it towards a draw.
+
+use crate::{EvalPosition, Position};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// What a recognizer knows about a position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EndgameVerdict {
+    /// The position's evaluation is known, and the static evaluation should not be used.
+    Exact(f32),
+    /// The static evaluation should be multiplied by this factor, typically between 0.0 and 1.0.
+    Scale(f32),
+}
+
+/// Knowledge about some set of endgame positions.
+pub trait EndgameKnowledge<P> {
+    /// Returns what is known about the position, or None if this knowledge does not apply to it.
+    fn probe(&self, position: &P) -> Option<EndgameVerdict>;
+}
+
+impl<P, F: Fn(&P) -> Option<EndgameVerdict>> EndgameKnowledge<P> for F {
+    fn probe(&self, position: &P) -> Option<EndgameVerdict> {
+        self(position)
+    }
+}
+
+/// A game position that can summarize its material, such as the number of pieces of each type and color.
+pub trait MaterialSignature: Position {
+    /// The type for a material configuration.
+    type Signature: Hash + Eq;
+
+    /// Returns the material configuration of the position.
+    fn material_signature(&self) -> Self::Signature;
+}
+
+/// Endgame recognizers, registered by material configuration.
+pub struct EndgameRecognizers<P: MaterialSignature> {
+    recognizers: HashMap<P::Signature, Vec<Box<dyn EndgameKnowledge<P> + Send + Sync>>>,
+}
+
+impl<P: MaterialSignature> Default for EndgameRecognizers<P> {
+    fn default() -> Self {
+        EndgameRecognizers {
+            recognizers: HashMap::new(),
+        }
+    }
+}
+
+impl<P: MaterialSignature> EndgameRecognizers<P> {
+    /// Returns an empty set of recognizers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a recognizer for positions with the given material configuration.
+    /// Recognizers for the same configuration are probed in the order they were registered.
+    pub fn register<K>(&mut self, signature: P::Signature, recognizer: K)
+    where
+        K: EndgameKnowledge<P> + Send + Sync + 'static,
+    {
+        self.recognizers
+            .entry(signature)
+            .or_default()
+            .push(Box::new(recognizer))
+    }
+}
+
+impl<P: MaterialSignature> EndgameKnowledge<P> for EndgameRecognizers<P> {
+    /// Returns the verdict of the first applicable recognizer for the position's material configuration.
+    fn probe(&self, position: &P) -> Option<EndgameVerdict> {
+        self.recognizers
+            .get(&position.material_signature())?
+            .iter()
+            .find_map(|recognizer| recognizer.probe(position))
+    }
+}
+
+/// Evaluates the position, consulting the endgame knowledge before the static evaluation.
+/// The static evaluation is only computed if the knowledge does not give an exact score.
+pub fn eval_with_knowledge<P: EvalPosition, K: EndgameKnowledge<P> + ?Sized>(
+    position: &P,
+    knowledge: &K,
+) -> f32 {
+    match knowledge.probe(position) {
+        Some(EndgameVerdict::Exact(eval)) => eval,
+        Some(EndgameVerdict::Scale(factor)) => position.static_eval() * factor,
+        None => position.static_eval(),
+    }
+}
@@ -0,0 +1,96 @@
+//! Trivial engines, as baselines for the arena and smoke tests for new games.
+
+#[cfg(feature = "rand")]
+use crate::Position;
+use crate::{Engine, EvalPosition, GameResult};
+#[cfg(feature = "rand")]
+use rand::rngs::SmallRng;
+#[cfg(feature = "rand")]
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+
+/// Plays a uniformly random legal move. The same seed always produces the same moves. Available with the `rand` feature.
+#[cfg(feature = "rand")]
+#[derive(Clone, Debug)]
+pub struct RandomEngine {
+    rng: SmallRng,
+}
+
+#[cfg(feature = "rand")]
+impl RandomEngine {
+    pub fn new(seed: u64) -> Self {
+        RandomEngine {
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+impl<P: Position> Engine<P> for RandomEngine {
+    fn choose_move(&mut self, position: &P, _time_left: Duration, _increment: Duration) -> P::Move {
+        let mut moves = vec![];
+        position.generate_moves(&mut moves);
+        assert!(
+            !moves.is_empty(),
+            "RandomEngine called in a position with no legal moves"
+        );
+        moves.swap_remove(self.rng.gen_range(0..moves.len()))
+    }
+}
+
+/// Plays the move with the best static evaluation after it, looking one ply ahead. Always plays a winning move if there is one, and avoids losing moves if it can.
+///
+/// Ties are broken by move generation order.
+/// # Examples
+/// ```rust
+/// use board_game_traits::arena::{self, ArenaOptions};
+/// use board_game_traits::game::GameEndReason;
+/// use board_game_traits::clock::{DefaultTimeSource, TimeControl};
+/// use board_game_traits::engines::GreedyEngine;
+/// use board_game_traits::testing::Nim;
+/// use board_game_traits::{GameResult, Position};
+/// use std::time::Duration;
+///
+/// // Nim's evaluation is perfect, so the greedy engine plays perfectly, and white wins from 10 stones
+/// let options = ArenaOptions {
+///     time_control: TimeControl::new(Duration::from_secs(10), Duration::ZERO),
+///     max_plies: None,
+/// };
+/// let game = arena::play_game(
+///     &mut GreedyEngine,
+///     &mut GreedyEngine,
+///     &Nim::start_position(),
+///     &[],
+///     &options,
+///     &DefaultTimeSource::default(),
+/// );
+/// assert_eq!((game.result, game.reason), (GameResult::WhiteWin, GameEndReason::Rules));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GreedyEngine;
+
+impl<P: EvalPosition> Engine<P> for GreedyEngine {
+    fn choose_move(&mut self, position: &P, _time_left: Duration, _increment: Duration) -> P::Move {
+        let side_to_move = position.side_to_move();
+        let mut moves = vec![];
+        position.generate_moves(&mut moves);
+        let mut position = position.clone();
+        let mut best_move = None;
+        let mut best_score = f32::NEG_INFINITY;
+        for mv in moves {
+            let reverse_move = position.do_move(mv.clone());
+            let score = match position.game_result() {
+                Some(GameResult::Draw) => 0.0,
+                Some(result) if result == GameResult::win_by(side_to_move) => f32::INFINITY,
+                Some(_) => f32::NEG_INFINITY,
+                None => position.static_eval() * side_to_move.multiplier() as f32,
+            };
+            position.reverse_move(reverse_move);
+            if best_move.is_none() || score > best_score {
+                best_move = Some(mv);
+                best_score = score;
+            }
+        }
+        best_move.expect("GreedyEngine called in a position with no legal moves")
+    }
+}
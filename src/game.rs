@@ -0,0 +1,194 @@
+//! Records of complete games, and how games end.
+
+use crate::{ClaimableDraws, DrawClaim, GameResult, Position, ReplayError};
+#[cfg(feature = "rand")]
+use rand::Rng;
+
+/// How a game ended, in the arena, on a clock, or over the network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameEndReason {
+    /// The game was decided by its rules.
+    Rules,
+    /// The game was not decided, but the side to move had no legal moves. Scored as a draw.
+    NoLegalMoves,
+    /// The side to move ran out of time.
+    Timeout,
+    /// The side to move played an illegal move.
+    IllegalMove,
+    /// The side to move's engine panicked while choosing a move.
+    Crash,
+    /// The side to move claimed a draw. Only happens in `arena::play_game_claiming_draws`.
+    DrawClaimed(DrawClaim),
+    /// Too many plies were played without an irreversible move, and the game was adjudicated as a draw. See `arena::no_progress_adjudicator`.
+    NoProgress,
+    /// Enough consecutive passes were played to end the game, and it was scored with `score_after_passes`. See `arena::passes_adjudicator`.
+    ConsecutivePasses,
+    /// The side to move resigned. Only happens in network games.
+    Resignation,
+    /// The side to move disconnected, or broke the protocol. Only happens in network games.
+    Disconnected,
+    /// The game reached the maximum number of plies in the arena options, or the game's `MAX_GAME_LENGTH`, and was adjudicated as a draw.
+    MaxPlies,
+}
+
+/// The result of a finished game, and how it ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameOutcome {
+    pub result: GameResult,
+    pub reason: GameEndReason,
+}
+
+/// A record of a game: its start position and the moves played from it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Game<P: Position> {
+    pub start_position: P,
+    pub moves: Vec<P::Move>,
+    /// The result of the game, or None if it was not decided.
+    pub result: Option<GameResult>,
+    /// The draw claim that ended the game, if any. The result is then a draw, although the final position's `game_result` is None.
+    pub draw_claim: Option<DrawClaim>,
+}
+
+impl<P: Position + Clone> Game<P> {
+    /// Creates a record of the given moves from the start position, checking that every move is legal. The result is taken from the final position.
+    pub fn from_moves(settings: &P::Settings, moves: Vec<P::Move>) -> Result<Self, ReplayError> {
+        let position = P::from_moves(settings, &moves)?;
+        Ok(Game {
+            start_position: P::start_position_with_settings(settings),
+            moves,
+            result: position.game_result(),
+            draw_claim: None,
+        })
+    }
+
+    /// Returns the position after all the moves have been played.
+    pub fn final_position(&self) -> P {
+        let mut position = self.start_position.clone();
+        for mv in self.moves.iter() {
+            position.do_move(mv.clone());
+        }
+        position
+    }
+}
+
+impl<P: ClaimableDraws + Clone> Game<P> {
+    /// Ends the game with a draw claim by the side to move in the final position, if the claim is valid. Returns whether it was.
+    pub fn claim_draw(&mut self, claim: DrawClaim) -> bool {
+        if self.result.is_some() {
+            return false;
+        }
+        match self.final_position().claim_draw(claim) {
+            Some(result) => {
+                self.result = Some(result);
+                self.draw_claim = Some(claim);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Plays uniformly random legal moves from the start position, until the game is decided, there are no legal moves, or `max_plies` moves have been played. Never plays more than `P::MAX_GAME_LENGTH` moves.
+///
+/// The same random number generator state always produces the same game. Available with the `rand` feature.
+#[cfg(feature = "rand")]
+pub fn random_game<P, R>(settings: &P::Settings, rng: &mut R, max_plies: u32) -> Game<P>
+where
+    P: Position + Clone,
+    R: Rng,
+{
+    let start_position = P::start_position_with_settings(settings);
+    let mut position = start_position.clone();
+    let mut game_moves = vec![];
+    let mut moves = vec![];
+    let max_plies = crate::ply_limit::<P>(max_plies) as usize;
+    while game_moves.len() < max_plies && position.game_result().is_none() {
+        moves.clear();
+        position.generate_moves(&mut moves);
+        if moves.is_empty() {
+            break;
+        }
+        let mv = moves.swap_remove(rng.gen_range(0..moves.len()));
+        position.do_move(mv.clone());
+        game_moves.push(mv);
+    }
+    Game {
+        start_position,
+        moves: game_moves,
+        result: position.game_result(),
+        draw_claim: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Nim;
+    use crate::Color;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    /// Nim, where the game is adjudicated after 3 plies.
+    #[derive(Clone, Debug, PartialEq)]
+    struct ShortNim(Nim);
+
+    impl Position for ShortNim {
+        type Move = u32;
+        type ReverseMove = u32;
+        type Settings = ();
+
+        fn start_position_with_settings(settings: &()) -> Self {
+            ShortNim(Nim::start_position_with_settings(settings))
+        }
+
+        fn side_to_move(&self) -> Color {
+            self.0.side_to_move()
+        }
+
+        fn generate_moves<E: Extend<u32>>(&self, moves: &mut E) {
+            self.0.generate_moves(moves)
+        }
+
+        fn do_move(&mut self, mv: u32) -> u32 {
+            self.0.do_move(mv)
+        }
+
+        fn reverse_move(&mut self, mv: u32) {
+            self.0.reverse_move(mv)
+        }
+
+        fn game_result(&self) -> Option<GameResult> {
+            self.0.game_result()
+        }
+
+        const MAX_GAME_LENGTH: Option<u32> = Some(3);
+    }
+
+    #[test]
+    fn random_game_is_played_to_the_end() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let game = random_game::<Nim, _>(&(), &mut rng, 100);
+        assert_eq!(game.moves.iter().sum::<u32>(), 10);
+        assert!(game.result.is_some());
+        assert_eq!(game.final_position().game_result(), game.result);
+    }
+
+    #[test]
+    fn random_game_stops_at_max_plies() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let game = random_game::<Nim, _>(&(), &mut rng, 2);
+        assert_eq!(game.moves.len(), 2);
+        assert_eq!(game.result, None);
+    }
+
+    #[test]
+    fn random_game_stops_at_max_game_length() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        for _ in 0..10 {
+            let game = random_game::<ShortNim, _>(&(), &mut rng, 100);
+            assert_eq!(game.moves.len(), 3);
+        }
+    }
+}
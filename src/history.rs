@@ -0,0 +1,330 @@
+//! Adding move history to positions that do not store it.
+
+use crate::{
+    ClaimableDraws, Color, DrawClaim, EvalPosition, GameResult, HashablePosition, HistoryAware,
+    PassablePosition, Position, ProgressCounter, PruningHints,
+};
+
+/// Wraps a position, and records every move played through it.
+///
+/// Implements `HistoryAware` for any position. The history is not part of the position's identity, so the wrapper hashes like the wrapped position.
+/// The wrapper keeps the wrapped position's reverse moves itself, so that it can look back through the history, for example to count repetitions.
+/// The wrapper also implements the other traits that the arena and search tools use, such as `EvalPosition`, `ProgressCounter`, `ClaimableDraws` and `PassablePosition`, whenever the wrapped position does.
+/// # Examples
+/// ```rust
+/// use board_game_traits::history::PositionWithHistory;
+/// use board_game_traits::{HistoryAware, Position};
+/// # use board_game_traits::{Color, GameResult};
+/// # use board_game_traits::testing::Nim;
+/// let mut position = PositionWithHistory::<Nim>::start_position();
+/// position.do_move(2);
+/// let reverse_move = position.do_move(3);
+/// assert_eq!((position.last_move(), position.ply(), position.move_number()), (Some(&3), 2, 2));
+///
+/// position.reverse_move(reverse_move);
+/// assert_eq!((position.last_move(), position.ply(), position.move_number()), (Some(&2), 1, 1));
+/// ```
+#[derive(Clone, Debug)]
+pub struct PositionWithHistory<P: Position> {
+    position: P,
+    start_ply: usize,
+    moves: Vec<P::Move>,
+    reverse_moves: Vec<P::ReverseMove>,
+}
+
+impl<P: Position> PositionWithHistory<P> {
+    /// Wraps a position, counting plies from the start of the game.
+    pub fn new(position: P) -> Self {
+        Self::with_start_ply(position, 0)
+    }
+
+    /// Wraps a position that was reached after `start_ply` plies, whose moves are not known.
+    pub fn with_start_ply(position: P, start_ply: usize) -> Self {
+        PositionWithHistory {
+            position,
+            start_ply,
+            moves: vec![],
+            reverse_moves: vec![],
+        }
+    }
+
+    /// Returns the wrapped position.
+    pub fn position(&self) -> &P {
+        &self.position
+    }
+
+    /// Returns the moves played through the wrapper, in order.
+    pub fn moves(&self) -> &[P::Move] {
+        &self.moves
+    }
+
+    /// Returns the wrapped position, discarding the history.
+    pub fn into_inner(self) -> P {
+        self.position
+    }
+}
+
+impl<P: HashablePosition + ProgressCounter> PositionWithHistory<P> {
+    /// Returns the number of times the current position occurred earlier in the game.
+    ///
+    /// Only the plies since the last irreversible move are scanned, as no earlier position can occur again. Positions from before the wrapper was created are not known.
+    /// The moves are taken back and replayed on the wrapped position, which is left unchanged.
+    /// # Examples
+    /// ```rust
+    /// use board_game_traits::history::PositionWithHistory;
+    /// use board_game_traits::testing::Walk;
+    /// use board_game_traits::Position;
+    /// let mut position = PositionWithHistory::<Walk>::start_position();
+    /// for mv in [1, -1, 0, 1, -1].iter() {
+    ///     position.do_move(*mv);
+    /// }
+    /// // The position after the mark occurred once before, and earlier positions are not scanned
+    /// assert_eq!(position.repetitions(), 1);
+    /// ```
+    pub fn repetitions(&mut self) -> usize {
+        let current = self.position.hash_position();
+        let mut repetitions = 0;
+        let mut taken_back = 0;
+        while let Some(reverse_move) = self.reverse_moves.pop() {
+            self.position.reverse_move(reverse_move);
+            taken_back += 1;
+            if self
+                .position
+                .is_irreversible(&self.moves[self.moves.len() - taken_back])
+            {
+                break;
+            }
+            if self.position.hash_position() == current {
+                repetitions += 1;
+            }
+        }
+        for i in self.moves.len() - taken_back..self.moves.len() {
+            let reverse_move = self.position.do_move(self.moves[i].clone());
+            self.reverse_moves.push(reverse_move);
+        }
+        repetitions
+    }
+}
+
+/// Positions are equal if the wrapped positions are, regardless of how they were reached, to match how the wrapper hashes.
+impl<P: Position + PartialEq> PartialEq for PositionWithHistory<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.position == other.position
+    }
+}
+
+impl<P: Position> Position for PositionWithHistory<P> {
+    type Move = P::Move;
+    /// The wrapped position's reverse moves are kept by the wrapper.
+    type ReverseMove = ();
+    type Settings = P::Settings;
+
+    fn start_position_with_settings(settings: &Self::Settings) -> Self {
+        PositionWithHistory::new(P::start_position_with_settings(settings))
+    }
+
+    fn side_to_move(&self) -> Color {
+        self.position.side_to_move()
+    }
+
+    fn generate_moves<E: Extend<Self::Move>>(&self, moves: &mut E) {
+        self.position.generate_moves(moves)
+    }
+
+    fn move_is_legal(&self, mv: Self::Move) -> bool {
+        self.position.move_is_legal(mv)
+    }
+
+    fn do_move(&mut self, mv: Self::Move) {
+        self.moves.push(mv.clone());
+        let reverse_move = self.position.do_move(mv);
+        self.reverse_moves.push(reverse_move);
+    }
+
+    fn reverse_move(&mut self, _mv: ()) {
+        let reverse_move = self.reverse_moves.pop().expect("No move to take back");
+        self.moves.pop();
+        self.position.reverse_move(reverse_move)
+    }
+
+    fn game_result(&self) -> Option<GameResult> {
+        self.position.game_result()
+    }
+
+    const MAX_GAME_LENGTH: Option<u32> = P::MAX_GAME_LENGTH;
+}
+
+impl<P: Position> HistoryAware for PositionWithHistory<P> {
+    fn last_move(&self) -> Option<&Self::Move> {
+        self.moves.last()
+    }
+
+    fn ply(&self) -> usize {
+        self.start_ply + self.moves.len()
+    }
+}
+
+impl<P: EvalPosition> EvalPosition for PositionWithHistory<P>
+where
+    P::ReverseMove: Clone,
+{
+    fn static_eval(&self) -> f32 {
+        self.position.static_eval()
+    }
+
+    fn pruning_profile(&self) -> PruningHints {
+        self.position.pruning_profile()
+    }
+
+    const BRANCH_FACTOR: u64 = P::BRANCH_FACTOR;
+}
+
+impl<P: HashablePosition> HashablePosition for PositionWithHistory<P> {
+    type HashPosition = P::HashPosition;
+
+    fn hash_position(&self) -> Self::HashPosition {
+        self.position.hash_position()
+    }
+}
+
+impl<P: ProgressCounter> ProgressCounter for PositionWithHistory<P> {
+    fn halfmove_clock(&self) -> u32 {
+        self.position.halfmove_clock()
+    }
+
+    fn is_irreversible(&self, mv: &Self::Move) -> bool {
+        self.position.is_irreversible(mv)
+    }
+}
+
+impl<P: ClaimableDraws> ClaimableDraws for PositionWithHistory<P> {
+    fn available_draw_claims(&self) -> Vec<DrawClaim> {
+        self.position.available_draw_claims()
+    }
+
+    fn claim_draw(&self, claim: DrawClaim) -> Option<GameResult> {
+        self.position.claim_draw(claim)
+    }
+}
+
+impl<P: PassablePosition> PassablePosition for PositionWithHistory<P> {
+    const PASSES_TO_END: u32 = P::PASSES_TO_END;
+
+    fn pass_move() -> Self::Move {
+        P::pass_move()
+    }
+
+    fn can_pass(&self) -> bool {
+        self.position.can_pass()
+    }
+
+    fn is_pass(mv: &Self::Move) -> bool {
+        P::is_pass(mv)
+    }
+
+    fn consecutive_passes(&self) -> u32 {
+        self.position.consecutive_passes()
+    }
+
+    fn score_after_passes(&self) -> GameResult {
+        self.position.score_after_passes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Nim, Walk};
+
+    #[test]
+    fn positions_reached_differently_are_equal() {
+        let mut position = PositionWithHistory::<Nim>::start_position();
+        position.do_move(1);
+        position.do_move(2);
+        let mut transposed = PositionWithHistory::with_start_ply(Nim::start_position(), 4);
+        transposed.do_move(2);
+        transposed.do_move(1);
+        assert_eq!(position, transposed);
+
+        transposed.do_move(1);
+        assert_ne!(position, transposed);
+    }
+
+    #[test]
+    fn repetitions_stop_at_the_last_irreversible_move() {
+        let mut position = PositionWithHistory::<Walk>::start_position();
+        for mv in [1, -1, 1, -1].iter() {
+            position.do_move(*mv);
+        }
+        assert_eq!(position.repetitions(), 2);
+
+        position.do_move(0);
+        assert_eq!(position.repetitions(), 0);
+        position.do_move(1);
+        position.do_move(-1);
+        assert_eq!(position.repetitions(), 1);
+
+        // The scan leaves the position and its history unchanged
+        assert_eq!(position.moves(), [1, -1, 1, -1, 0, 1, -1]);
+        assert_eq!(position.halfmove_clock(), 2);
+        position.reverse_move(());
+        assert_eq!(position.position().square, 1);
+    }
+
+    /// Walk, where marks do not count for repetitions, so that only the irreversible move stops the scan.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Unmarked(Walk);
+
+    impl Position for Unmarked {
+        type Move = i32;
+        type ReverseMove = (i32, u32);
+        type Settings = ();
+        fn start_position_with_settings(_: &()) -> Self {
+            Unmarked(Walk::start_position())
+        }
+        fn side_to_move(&self) -> Color {
+            self.0.side_to_move()
+        }
+        fn generate_moves<E: Extend<i32>>(&self, moves: &mut E) {
+            self.0.generate_moves(moves)
+        }
+        fn do_move(&mut self, mv: i32) -> (i32, u32) {
+            self.0.do_move(mv)
+        }
+        fn reverse_move(&mut self, mv: (i32, u32)) {
+            self.0.reverse_move(mv)
+        }
+        fn game_result(&self) -> Option<GameResult> {
+            None
+        }
+    }
+
+    impl HashablePosition for Unmarked {
+        type HashPosition = (i32, Color);
+        fn hash_position(&self) -> (i32, Color) {
+            (self.0.square, self.0.side_to_move)
+        }
+    }
+
+    impl ProgressCounter for Unmarked {
+        fn halfmove_clock(&self) -> u32 {
+            self.0.halfmove_clock()
+        }
+        fn is_irreversible(&self, mv: &i32) -> bool {
+            self.0.is_irreversible(mv)
+        }
+    }
+
+    #[test]
+    fn repetitions_before_an_irreversible_move_are_not_counted() {
+        let mut position = PositionWithHistory::<Unmarked>::start_position();
+        for mv in [1, -1, 0, 0].iter() {
+            position.do_move(*mv);
+        }
+        // The start position has the same hash, but comes before the marks
+        assert_eq!(position.repetitions(), 0);
+        position.do_move(1);
+        position.do_move(-1);
+        assert_eq!(position.repetitions(), 1);
+    }
+}
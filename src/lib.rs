@@ -3,13 +3,51 @@
 //! General game-agnostic tools and engines can be built on this module
 //! Represents any 2-player sequential, deterministic, perfect-information game. This includes many popular games such as chess, go, xiangqi, othello, connect four and tic-tac-toe.
 
+pub mod annotation;
+pub mod arena;
+#[cfg(feature = "rand")]
+pub mod bench;
+pub mod clock;
+pub mod database;
+pub mod endgame;
+pub mod engines;
+pub mod game;
+pub mod history;
+pub mod mirror;
+#[cfg(feature = "net")]
+pub mod net;
+pub mod perft;
+#[cfg(feature = "rand")]
+pub mod rollout;
+pub mod search;
+pub mod settings;
+pub mod solver;
+pub mod stats;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+pub mod tablebase;
+#[cfg(feature = "test-utils")]
+pub mod testing;
+pub mod tournament;
+pub mod tuning;
+pub mod v2;
+
+#[cfg(feature = "derive")]
+pub use board_game_traits_derive::{DelegateEvalPosition, DelegatePosition};
+
 use self::Color::*;
+#[cfg(feature = "rand")]
+use rand::Rng;
+use std::convert::TryFrom;
 use std::fmt;
 use std::hash;
 use std::ops;
+use std::str::FromStr;
+use std::time::Duration;
 
 /// Represents a player's color.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
     White = 0,
     Black = 1,
@@ -60,10 +98,77 @@ impl Color {
     pub fn multiplier(self) -> isize {
         self as u16 as isize * -2 + 1
     }
+
+    /// Both colors, in discriminant order.
+    pub const ALL: [Color; 2] = [White, Black];
+
+    /// Returns an iterator over both colors, starting with white.
+    /// # Examples
+    /// ```rust
+    /// use board_game_traits::Color;
+    /// assert_eq!(Color::iter().collect::<Vec<_>>(), vec![Color::White, Color::Black]);
+    /// ```
+    #[inline]
+    pub fn iter() -> impl Iterator<Item = Color> {
+        Self::ALL.iter().copied()
+    }
+
+    /// Returns the color with the given discriminant, or `None` if it is not 0 or 1.
+    /// # Examples
+    /// ```rust
+    /// use board_game_traits::Color;
+    /// assert_eq!(Color::from_disc(1), Some(Color::Black));
+    /// assert_eq!(Color::from_disc(2), None);
+    /// ```
+    #[inline]
+    pub fn from_disc(disc: usize) -> Option<Self> {
+        match disc {
+            0 => Some(White),
+            1 => Some(Black),
+            _ => None,
+        }
+    }
+
+    /// Returns `white_value` for white, and `black_value` for black.
+    /// # Examples
+    /// ```rust
+    /// use board_game_traits::Color;
+    /// assert_eq!(Color::White.relative(1, 8), 1);
+    /// assert_eq!(Color::Black.relative(1, 8), 8);
+    /// ```
+    #[inline]
+    pub fn relative<T>(self, white_value: T, black_value: T) -> T {
+        match self {
+            White => white_value,
+            Black => black_value,
+        }
+    }
+}
+
+/// The error returned when converting an invalid discriminant to a `Color`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InvalidColorError(pub usize);
+
+impl fmt::Display for InvalidColorError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{} is not a valid color discriminant", self.0)
+    }
+}
+
+impl std::error::Error for InvalidColorError {}
+
+impl TryFrom<usize> for Color {
+    type Error = InvalidColorError;
+
+    #[inline]
+    fn try_from(disc: usize) -> Result<Self, Self::Error> {
+        Color::from_disc(disc).ok_or(InvalidColorError(disc))
+    }
 }
 
 /// The result of a game after it has finished.
 #[derive(PartialEq, Eq, Clone, Debug, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameResult {
     WhiteWin = 0,
     BlackWin = 1,
@@ -139,31 +244,238 @@ pub trait Position: Sized {
     /// If the winning player always plays the last move (as in chess), implementations are allowed
     /// to only return a win when the losing player is to move.
     fn game_result(&self) -> Option<GameResult>;
+
+    /// The maximum number of plies in a game, or None if the rules guarantee that every game ends.
+    ///
+    /// Games that can continue forever, for example by repeating moves, should set a limit. Rollouts, the arena and the solver adjudicate a game that reaches the limit as a draw.
+    /// Tools that do not know how many plies have already been played count from the position they are given.
+    const MAX_GAME_LENGTH: Option<u32> = None;
+
+    /// Returns the start position with the given settings, after playing the given moves.
+    ///
+    /// Every move is checked with `move_is_legal` before it is played. Fails at the first illegal move, or the first move after the game is decided.
+    fn from_moves(settings: &Self::Settings, moves: &[Self::Move]) -> Result<Self, ReplayError> {
+        let mut position = Self::start_position_with_settings(settings);
+        for (ply, mv) in moves.iter().enumerate() {
+            replay_move(&mut position, ply, mv.clone(), || format!("{:?}", mv))?;
+        }
+        Ok(position)
+    }
+
+    /// Like `from_moves`, but parses each move from its notation first.
+    /// # Examples
+    /// ```rust
+    /// use board_game_traits::{Position, ReplayErrorKind};
+    /// # use board_game_traits::{Color, GameResult};
+    /// # use board_game_traits::testing::Nim;
+    /// let position = Nim::from_move_strings(&(), &["3", "2"]).unwrap();
+    /// assert_eq!(position.0, 5);
+    ///
+    /// let error = Nim::from_move_strings(&(), &["3", "three"]).err().unwrap();
+    /// assert_eq!((error.ply, error.kind), (1, ReplayErrorKind::Unparsable));
+    ///
+    /// let error = Nim::from_move_strings(&(), &["3", "3", "4"]).err().unwrap();
+    /// assert_eq!((error.ply, error.kind), (2, ReplayErrorKind::Illegal));
+    /// ```
+    fn from_move_strings<S: AsRef<str>>(
+        settings: &Self::Settings,
+        moves: &[S],
+    ) -> Result<Self, ReplayError>
+    where
+        Self::Move: FromStr,
+    {
+        let mut position = Self::start_position_with_settings(settings);
+        for (ply, notation) in moves.iter().enumerate() {
+            let notation = notation.as_ref();
+            let mv = notation.parse().map_err(|_| ReplayError {
+                ply,
+                mv: notation.to_string(),
+                kind: ReplayErrorKind::Unparsable,
+            })?;
+            replay_move(&mut position, ply, mv, || notation.to_string())?;
+        }
+        Ok(position)
+    }
 }
 
+/// Returns the lower of `max_plies` and the game's maximum length.
+pub(crate) fn ply_limit<P: Position>(max_plies: u32) -> u32 {
+    P::MAX_GAME_LENGTH.map_or(max_plies, |length| length.min(max_plies))
+}
+
+fn replay_move<P: Position, F: FnOnce() -> String>(
+    position: &mut P,
+    ply: usize,
+    mv: P::Move,
+    describe: F,
+) -> Result<(), ReplayError> {
+    let kind = if position.game_result().is_some() {
+        ReplayErrorKind::GameOver
+    } else if !position.move_is_legal(mv.clone()) {
+        ReplayErrorKind::Illegal
+    } else {
+        position.do_move(mv);
+        return Ok(());
+    };
+    Err(ReplayError {
+        ply,
+        mv: describe(),
+        kind,
+    })
+}
+
+/// Why a move could not be replayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReplayErrorKind {
+    /// The move's notation could not be parsed.
+    Unparsable,
+    /// The move is not legal in the position.
+    Illegal,
+    /// The game was already decided before the move.
+    GameOver,
+}
+
+/// The error returned when a sequence of moves cannot be replayed, such as by `Position::from_moves`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ReplayError {
+    /// The index of the failing move, starting from 0.
+    pub ply: usize,
+    /// The failing move, as its notation if it was parsed from one, otherwise in its `Debug` form.
+    pub mv: String,
+    pub kind: ReplayErrorKind,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let reason = match self.kind {
+            ReplayErrorKind::Unparsable => "could not be parsed",
+            ReplayErrorKind::Illegal => "is illegal",
+            ReplayErrorKind::GameOver => "was played after the game ended",
+        };
+        write!(fmt, "Move {} at ply {} {}", self.mv, self.ply, reason)
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
 /// A game position that also includes a heuristic static evaluation function.
 /// Enables the use of many game-playing algorithms, such as minimax.
 pub trait EvalPosition: Position + PartialEq + Clone {
     /// A fast, static evaluation of the current position.
     /// Returns a number between -100 and 100, where 0.0 is a draw, positive number means better for white, and negative number means better for black.
     fn static_eval(&self) -> f32;
+
+    /// Returns the static evaluation from the perspective of the side to move: positive numbers are better for the side to move.
+    /// Negamax searches should use this instead of flipping the sign of `static_eval` themselves.
+    fn eval_relative(&self) -> f32 {
+        self.static_eval() * self.side_to_move().multiplier() as f32
+    }
+
+    /// Returns an estimate for the average branch factor of the game.
+    /// Helps search algorithms guide pruning and time management.
+    const BRANCH_FACTOR: u64 = 20;
+
+    /// Returns hints for how aggressively search algorithms can prune in this position.
+    /// The default uses `BRANCH_FACTOR` for every position, with no extra volatility.
+    fn pruning_profile(&self) -> PruningHints {
+        PruningHints::new(Self::BRANCH_FACTOR as f32, 0.0)
+    }
 }
 
-/// An extended game representation, which includes many additional methods to help game-playing algorithms search more effectively.
-pub trait ExtendedPosition: EvalPosition {
-    /// The type for a reverse null move
-    type ReverseNullMove;
+/// Per-position hints for late move reductions and move count pruning.
+///
+/// Positions with many moves can be reduced and pruned more aggressively, while tactically volatile positions, for example in check or with many captures available, should be searched more fully.
+/// # Examples
+/// ```rust
+/// use board_game_traits::PruningHints;
+/// let quiet = PruningHints::new(30.0, 0.0);
+/// let tactical = PruningHints::new(30.0, 1.0);
+/// assert!(quiet.late_move_reduction(8, 20) > tactical.late_move_reduction(8, 20));
+/// assert!(quiet.move_count_limit(3) < tactical.move_count_limit(3));
+/// assert_eq!(quiet.late_move_reduction(2, 20), 0);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PruningHints {
+    /// The expected number of legal moves in the position's subtree.
+    pub expected_branching: f32,
+    /// How tactical the position is, between 0 (quiet) and 1 (very volatile).
+    pub volatility: f32,
+}
+
+impl PruningHints {
+    pub fn new(expected_branching: f32, volatility: f32) -> Self {
+        PruningHints {
+            expected_branching,
+            volatility,
+        }
+    }
+
+    /// Returns how many plies to reduce the `move_number`th move (counting from 1) at the given remaining depth. Returns 0 for the first moves, and at shallow depths.
+    pub fn late_move_reduction(&self, depth: u16, move_number: usize) -> u16 {
+        if depth < 3 || move_number < 3 {
+            return 0;
+        }
+        let base = 0.5 + (depth as f32).ln() * (move_number as f32).ln() / 2.25;
+        let reduction =
+            base * self.branching_scale() * (1.0 - self.volatility.clamp(0.0, 1.0) / 2.0);
+        (reduction as u16).min(depth - 1)
+    }
+
+    /// Returns the number of moves to search at the given remaining depth, after which the remaining quiet moves can be pruned.
+    pub fn move_count_limit(&self, depth: u16) -> usize {
+        let base = 3.0 + (depth as f32).powi(2);
+        let limit = base * self.branching_scale() * (1.0 + self.volatility.clamp(0.0, 1.0));
+        (limit as usize).max(1)
+    }
 
+    /// Scales pruning relative to a branch factor of 20, the default `BRANCH_FACTOR`.
+    fn branching_scale(&self) -> f32 {
+        self.expected_branching.max(2.0).ln() / 20.0_f32.ln()
+    }
+}
+
+/// A game position that can be hashed, for transposition tables and other caches.
+pub trait HashablePosition: Position {
     /// A representation of the position that can be hashed. Can be Self, or unit if no hashing is desired.
     type HashPosition: hash::Hash + Eq;
 
     fn hash_position(&self) -> Self::HashPosition;
+}
 
+/// A game position that can generate only its "active" moves, for quiescence search.
+pub trait QuiescencePosition: Position {
     /// Generates only the "active" moves in a position, and appends them to the provided vector. These are moves that radically change the static evaluation of a position, e.g. captures or promotions in chess.
     /// Search algorithms may recursively search all active moves, so eventually, no moves will be appended.
     /// Required for search algorithms to use quiescence search.
     fn active_moves(&self, moves: &mut Vec<Self::Move>);
 
+    /// Returns the largest change in static evaluation that the active move could cause, for example the value of the captured piece plus any promotion.
+    /// Enables delta pruning: quiescence search can skip a move if the evaluation plus this swing still does not reach alpha.
+    /// The default never allows pruning.
+    fn max_eval_swing(&self, _mv: &Self::Move) -> f32 {
+        f32::INFINITY
+    }
+}
+
+/// An extended game representation, which includes many additional methods to help game-playing algorithms search more effectively.
+///
+/// This is an alias for positions that implement all of `EvalPosition`, `HashablePosition`, `QuiescencePosition` and `NullMovePosition`, and is implemented automatically for them. Tools should prefer to require only the traits they use.
+pub trait ExtendedPosition:
+    EvalPosition + HashablePosition + QuiescencePosition + NullMovePosition
+{
+}
+
+impl<P> ExtendedPosition for P where
+    P: EvalPosition + HashablePosition + QuiescencePosition + NullMovePosition
+{
+}
+
+/// A game position that supports passing "null moves", for the null move reduction heuristic.
+/// Games without a sensible null move, such as games where zugzwang is common, should not implement it.
+pub trait NullMovePosition: Position {
+    /// The type for a reverse null move
+    type ReverseNullMove;
+
     fn null_move_is_available(&self) -> bool;
 
     /// Does a passing "null move".
@@ -172,8 +484,963 @@ pub trait ExtendedPosition: EvalPosition {
 
     /// Reverses a passing "null move".
     fn reverse_null_move(&mut self, reverse_move: Self::ReverseNullMove);
+}
 
-    /// Returns an estimate for the average branch factor of the game.
-    /// Helps search algorithms guide pruning and time management.
-    const BRANCH_FACTOR: u64 = 20;
+/// The contents of a single square that differ between two positions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SquareChange<Square, Piece> {
+    pub square: Square,
+    /// The square's contents in the old position, or `None` if it was empty.
+    pub before: Option<Piece>,
+    /// The square's contents in the new position, or `None` if it is empty.
+    pub after: Option<Piece>,
+}
+
+/// A game position that can list the squares that differ from another position.
+/// Enables graphical frontends to redraw or animate only the changed squares.
+pub trait PositionDiff: Position {
+    /// The type for a square on the board.
+    type Square;
+    /// The type for the contents of a non-empty square.
+    type Piece;
+
+    /// Returns every square whose contents differ between `self` and `other`, in no particular order.
+    /// `before` holds the contents in `self`, and `after` the contents in `other`.
+    fn diff(&self, other: &Self) -> Vec<SquareChange<Self::Square, Self::Piece>>;
+
+    /// Returns every square whose contents are changed by playing the move in this position, in no particular order.
+    /// `before` holds the contents before the move, and `after` the contents after it.
+    ///
+    /// The default plays the move on a copy of the position, and diffs the two. Implementations can override it to compute the changes from the move directly.
+    fn move_diff(&self, mv: &Self::Move) -> Vec<SquareChange<Self::Square, Self::Piece>>
+    where
+        Self: Clone,
+    {
+        let mut after = self.clone();
+        after.do_move(mv.clone());
+        self.diff(&after)
+    }
+}
+
+/// The result of a game with any number of players, after it has finished.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MultiPlayerResult<Player> {
+    /// The players' final placings, from first to last. Players that share a placing are grouped together.
+    Ranking(Vec<Vec<Player>>),
+    /// A final score for each player, where a higher score is better.
+    Scores(Vec<(Player, f32)>),
+}
+
+impl From<GameResult> for MultiPlayerResult<Color> {
+    fn from(result: GameResult) -> Self {
+        match result {
+            GameResult::WhiteWin => MultiPlayerResult::Ranking(vec![vec![White], vec![Black]]),
+            GameResult::BlackWin => MultiPlayerResult::Ranking(vec![vec![Black], vec![White]]),
+            GameResult::Draw => MultiPlayerResult::Ranking(vec![vec![White, Black]]),
+        }
+    }
+}
+
+/// The counterpart of `Position` for sequential games with any number of players.
+///
+/// Any 2-player `Position` can be used where a `MultiPlayerPosition` is expected, by wrapping it in a `TwoPlayer`.
+pub trait MultiPlayerPosition: Sized {
+    /// The type for players in the game.
+    type Player: Copy + Eq + fmt::Debug;
+    /// The type for moves in the game.
+    type Move: Eq + Clone + fmt::Debug;
+    /// The type for a reverse move in the game.
+    type ReverseMove;
+    /// Optional Settings when initializing the position.
+    type Settings: Default;
+
+    /// Returns the starting position for the game. This function always produces identical values.
+    #[inline]
+    fn start_position() -> Self {
+        Self::start_position_with_settings(&Self::Settings::default())
+    }
+
+    /// Returns the starting position for the game with the given settings.
+    fn start_position_with_settings(settings: &Self::Settings) -> Self;
+
+    /// Returns all players in the game, in turn order.
+    fn players(&self) -> Vec<Self::Player>;
+
+    /// Returns the player to move for the current position.
+    fn player_to_move(&self) -> Self::Player;
+
+    /// Generates all legal moves for the player to move, and extends the provided data structure with them.
+    fn generate_moves<E: Extend<Self::Move>>(&self, moves: &mut E);
+
+    /// Checks if a move is legal in the current position.
+    fn move_is_legal(&self, mv: Self::Move) -> bool {
+        let mut moves = vec![];
+        self.generate_moves(&mut moves);
+        moves.contains(&mv)
+    }
+
+    /// Plays a move in the position. Also returns a ReverseMove to take the move back.
+    ///
+    /// Doing and then undoing a move always restores the position to exactly the same state.
+    fn do_move(&mut self, mv: Self::Move) -> Self::ReverseMove;
+
+    /// Reverse a move made by `do_move`.
+    ///
+    /// Doing and then undoing a move always restores the position to exactly the same state.
+    fn reverse_move(&mut self, mv: Self::ReverseMove);
+
+    /// Returns the result if the game is decided, otherwise returns None.
+    fn game_result(&self) -> Option<MultiPlayerResult<Self::Player>>;
+}
+
+/// Wraps a 2-player `Position`, so that it can be used as a `MultiPlayerPosition` with `Color` as the player type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TwoPlayer<P>(pub P);
+
+impl<P: Position> MultiPlayerPosition for TwoPlayer<P> {
+    type Player = Color;
+    type Move = P::Move;
+    type ReverseMove = P::ReverseMove;
+    type Settings = P::Settings;
+
+    fn start_position_with_settings(settings: &Self::Settings) -> Self {
+        TwoPlayer(P::start_position_with_settings(settings))
+    }
+
+    fn players(&self) -> Vec<Color> {
+        Color::ALL.to_vec()
+    }
+
+    fn player_to_move(&self) -> Color {
+        self.0.side_to_move()
+    }
+
+    fn generate_moves<E: Extend<Self::Move>>(&self, moves: &mut E) {
+        self.0.generate_moves(moves)
+    }
+
+    fn move_is_legal(&self, mv: Self::Move) -> bool {
+        self.0.move_is_legal(mv)
+    }
+
+    fn do_move(&mut self, mv: Self::Move) -> Self::ReverseMove {
+        self.0.do_move(mv)
+    }
+
+    fn reverse_move(&mut self, mv: Self::ReverseMove) {
+        self.0.reverse_move(mv)
+    }
+
+    fn game_result(&self) -> Option<MultiPlayerResult<Color>> {
+        self.0.game_result().map(MultiPlayerResult::from)
+    }
+}
+
+/// A game position with chance events, such as dice rolls. At a chance node, the next state is decided by a random outcome instead of by a player's move.
+/// Enables algorithms such as expectiminimax to handle games like backgammon.
+pub trait StochasticPosition: Position {
+    /// The type for the outcome of a chance event, for example a dice roll.
+    type ChanceOutcome: Eq + Clone + fmt::Debug;
+    /// The type for reversing a chance outcome.
+    type ReverseChanceOutcome;
+
+    /// Returns true if the next state is decided by chance. No moves are generated at chance nodes.
+    fn is_chance_node(&self) -> bool;
+
+    /// Returns every possible outcome at a chance node, together with its probability. The probabilities sum to 1.
+    fn chance_outcomes(&self) -> Vec<(Self::ChanceOutcome, f64)>;
+
+    /// Applies the outcome of a chance event. Also returns a ReverseChanceOutcome to take it back.
+    ///
+    /// Applying and then reversing an outcome always restores the position to exactly the same state.
+    fn apply_chance_outcome(&mut self, outcome: Self::ChanceOutcome) -> Self::ReverseChanceOutcome;
+
+    /// Reverses an outcome applied by `apply_chance_outcome`.
+    fn reverse_chance_outcome(&mut self, reverse_outcome: Self::ReverseChanceOutcome);
+}
+
+/// A game position where some information is hidden from the players, such as cards in hand.
+///
+/// The position itself holds the full state. Algorithms such as information set MCTS search over determinizations: full states that are sampled to be consistent with what a player has observed.
+pub trait HiddenInfoPosition: Position {
+    /// The type for what a single player can see of the position.
+    type Observation;
+
+    /// Returns the part of the position that is visible to the given player.
+    fn observation_for(&self, color: Color) -> Self::Observation;
+
+    /// Samples a full state that is consistent with everything the side to move has observed.
+    /// The sampled state has the same observation as `self` for the side to move. Available with the `rand` feature.
+    #[cfg(feature = "rand")]
+    fn determinize<R: Rng>(&self, rng: &mut R) -> Self;
+}
+
+/// A 2-player game where both players choose their moves at the same time, without seeing the other's choice.
+/// The game advances when both moves are played together as a joint move.
+pub trait SimultaneousPosition: Sized {
+    /// The type for a single player's move in the game.
+    type Move: Eq + Clone + fmt::Debug;
+    /// The type for reversing a joint move.
+    type ReverseMove;
+    /// Optional Settings when initializing the position.
+    type Settings: Default;
+
+    /// Returns the starting position for the game. This function always produces identical values.
+    #[inline]
+    fn start_position() -> Self {
+        Self::start_position_with_settings(&Self::Settings::default())
+    }
+
+    /// Returns the starting position for the game with the given settings.
+    fn start_position_with_settings(settings: &Self::Settings) -> Self;
+
+    /// Generates all legal moves for the given player, and extends the provided data structure with them.
+    fn generate_moves_for<E: Extend<Self::Move>>(&self, color: Color, moves: &mut E);
+
+    /// Plays white's and black's moves together. Also returns a ReverseMove to take the joint move back.
+    ///
+    /// Doing and then undoing a joint move always restores the position to exactly the same state.
+    fn do_joint_move(&mut self, moves: (Self::Move, Self::Move)) -> Self::ReverseMove;
+
+    /// Reverses a joint move made by `do_joint_move`.
+    fn reverse_joint_move(&mut self, mv: Self::ReverseMove);
+
+    /// Returns the result if the game is decided, otherwise returns None.
+    fn game_result(&self) -> Option<GameResult>;
+}
+
+/// A game position that can generate pseudo-legal moves. These follow the movement rules of the game, but may still be illegal for reasons that are expensive to check, such as leaving the king in check in chess.
+/// Search algorithms can generate pseudo-legal moves cheaply, and only check legality for the moves they actually search.
+pub trait PseudoLegalPosition: Position {
+    /// Generates all pseudo-legal moves for the side to move, and extends the provided data structure with them.
+    /// This must include every move that `generate_moves` produces.
+    fn generate_pseudo_legal_moves<E: Extend<Self::Move>>(&self, moves: &mut E);
+
+    /// Checks if a pseudo-legal move is legal in the current position.
+    /// Only moves produced by `generate_pseudo_legal_moves` for this position may be passed to this function.
+    fn is_legal_after_pseudo(&self, mv: &Self::Move) -> bool;
+}
+
+/// A game position that can generate its moves in stages, for example captures before quiet moves in chess.
+/// Search algorithms can then search the most promising moves first, and skip generating the later stages if one of the early moves causes a cutoff.
+pub trait StagedMoveGen: Position {
+    /// The type for a move generation stage.
+    type Stage: Copy + Eq + fmt::Debug;
+
+    /// Returns the first stage of move generation for the current position.
+    fn first_stage(&self) -> Self::Stage;
+
+    /// Generates all legal moves in the given stage, and extends the provided data structure with them.
+    /// Returns the next stage, or None if this was the last stage.
+    ///
+    /// Together, the stages generate exactly the same moves as `generate_moves`, with no move generated twice.
+    fn next_stage_moves<E: Extend<Self::Move>>(
+        &self,
+        stage: Self::Stage,
+        moves: &mut E,
+    ) -> Option<Self::Stage>;
+}
+
+/// A game position that can estimate how promising each move is, so that search algorithms can search the best moves first.
+pub trait OrderedMoves: Position {
+    /// Returns a heuristic score for a legal move, where higher scores are searched first. For example, chess engines often score captures by most valuable victim, least valuable attacker (MVV-LVA).
+    /// The default implementation scores all moves equally.
+    fn move_heuristic_score(&self, _mv: &Self::Move) -> i32 {
+        0
+    }
+
+    /// Sorts the moves by descending heuristic score. Moves with equal scores keep their relative order.
+    fn sort_moves(&self, moves: &mut [Self::Move]) {
+        moves.sort_by_cached_key(|mv| std::cmp::Reverse(self.move_heuristic_score(mv)))
+    }
+}
+
+/// A game position that can statically evaluate the sequence of exchanges that a move starts, as in chess captures.
+/// Enables quiescence search to skip active moves that lose material.
+pub trait StaticExchange: Position {
+    /// Returns the expected material gain for the side to move from playing the move, assuming both sides then make only favourable recaptures on the same square.
+    /// Positive values are good for the side to move, and the unit is up to the implementation.
+    fn exchange_value(&self, mv: &Self::Move) -> i32;
+
+    /// Returns true if the move is expected to lose material.
+    fn is_losing_exchange(&self, mv: &Self::Move) -> bool {
+        self.exchange_value(mv) < 0
+    }
+}
+
+/// A game position whose moves can be packed into 32 bits.
+/// Enables transposition tables and opening books to store moves compactly.
+pub trait CompressedMove: Position {
+    /// Packs a move into 32 bits. The result only needs to be unique among the legal moves of a single position, so the position can be used to unpack it again.
+    fn compress(mv: &Self::Move) -> u32;
+
+    /// Unpacks a move packed by `compress`.
+    /// Returns None if the value does not represent a legal move in the current position, for example because of a hash collision in the storage structure.
+    fn decompress(&self, compressed: u32) -> Option<Self::Move>;
+}
+
+/// A game position that updates its static evaluation incrementally in `do_move` and `reverse_move`, instead of computing it from scratch.
+///
+/// Implementors promise that `static_eval` returns the incrementally updated value in constant time, and that this value always equals `full_static_eval`, up to floating point rounding. Search algorithms may then call `static_eval` at every node.
+pub trait IncrementalEval: EvalPosition {
+    /// Computes the static evaluation from scratch, ignoring the incrementally updated value.
+    fn full_static_eval(&self) -> f32;
+
+    /// Checks that the incrementally updated evaluation is within `tolerance` of a full recomputation.
+    /// Useful in debug assertions and tests.
+    fn incremental_eval_is_consistent(&self, tolerance: f32) -> bool {
+        (self.static_eval() - self.full_static_eval()).abs() <= tolerance
+    }
+}
+
+/// A game position whose evaluation function is controlled by a vector of numeric parameters, such as piece values.
+/// Enables generic tools to tune the evaluation of any game.
+pub trait TunableEval: EvalPosition {
+    /// Returns the number of evaluation parameters.
+    fn param_count() -> usize;
+
+    /// Returns the evaluation parameters currently used by `static_eval`. The vector always has length `param_count()`.
+    fn get_params(&self) -> Vec<f32>;
+
+    /// Sets the evaluation parameters used by `static_eval`.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `params` does not have length `param_count()`.
+    fn set_params(&mut self, params: &[f32]);
+}
+
+/// A game position that can be encoded as a tensor of floats, for input to neural networks.
+/// Typically, the tensor is a stack of planes, each holding one feature for every square on the board.
+pub trait TensorEncodable: Position {
+    /// The shape of the encoded tensor, for example `&[planes, height, width]`.
+    const SHAPE: &'static [usize];
+
+    /// Returns the number of values in the encoded tensor.
+    fn tensor_len() -> usize {
+        Self::SHAPE.iter().product()
+    }
+
+    /// Writes the position into `tensor`, in row-major order.
+    /// `tensor` has length `tensor_len()`, and is filled with zeros by the caller.
+    fn encode_into(&self, tensor: &mut [f32]);
+
+    /// Returns the position encoded as a tensor, in row-major order.
+    fn encode(&self) -> Vec<f32> {
+        let mut tensor = vec![0.0; Self::tensor_len()];
+        self.encode_into(&mut tensor);
+        tensor
+    }
+}
+
+/// A game position with a fixed, stable mapping between moves and indices, for example the outputs of a neural network's policy head.
+pub trait MoveIndex: Position {
+    /// The number of possible indices. Every index is smaller than this.
+    const ACTION_SPACE: usize;
+
+    /// Returns the index of a legal move in the current position.
+    /// Different legal moves in the same position always have different indices.
+    fn move_to_index(&self, mv: &Self::Move) -> usize;
+
+    /// Returns the legal move with the given index, or None if no legal move has that index.
+    fn index_to_move(&self, index: usize) -> Option<Self::Move>;
+
+    /// Returns a mask of length `ACTION_SPACE`, which is true for the indices of all legal moves.
+    fn legal_move_mask(&self) -> Vec<bool> {
+        let mut moves = vec![];
+        self.generate_moves(&mut moves);
+        let mut mask = vec![false; Self::ACTION_SPACE];
+        for mv in moves.iter() {
+            mask[self.move_to_index(mv)] = true;
+        }
+        mask
+    }
+}
+
+/// The evaluation of a single position by a policy-value evaluator, such as a neural network.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PolicyValue<M> {
+    /// The expected outcome of the game, between -1.0 (black wins) and 1.0 (white wins).
+    pub value: f32,
+    /// A prior probability for each legal move. The priors sum to 1.
+    pub priors: Vec<(M, f32)>,
+}
+
+/// An evaluator that evaluates many positions at once, as neural networks running on a GPU do most efficiently.
+pub trait BatchEval<P: Position> {
+    /// Evaluates every position, and returns the evaluations in the same order.
+    fn evaluate_batch(&self, positions: &[P]) -> Vec<PolicyValue<P::Move>>;
+
+    /// Returns the preferred maximum number of positions per batch. Callers may send smaller batches.
+    fn max_batch_size(&self) -> usize {
+        256
+    }
+
+    /// Evaluates a single position.
+    fn evaluate(&self, position: &P) -> PolicyValue<P::Move> {
+        self.evaluate_batch(std::slice::from_ref(position))
+            .pop()
+            .expect("evaluate_batch must return one evaluation per position")
+    }
+}
+
+/// A game position that can pick random moves, for playing out random games in Monte Carlo algorithms. Available with the `rand` feature.
+#[cfg(feature = "rand")]
+pub trait RolloutPosition: Position {
+    /// Returns a random legal move, or None if there are no legal moves.
+    /// The default implementation picks uniformly among all legal moves. Implementations may override it with a faster or better-informed choice.
+    fn random_move<R: Rng>(&self, rng: &mut R) -> Option<Self::Move> {
+        let mut moves = vec![];
+        self.generate_moves(&mut moves);
+        if moves.is_empty() {
+            None
+        } else {
+            Some(moves.swap_remove(rng.gen_range(0..moves.len())))
+        }
+    }
+}
+
+/// A game position that can enumerate the positions it can be reached from, by generating moves backwards.
+/// Enables retrograde analysis, such as building endgame tablebases.
+pub trait RetrogradePosition: Position {
+    /// Generates every position from which this position can be reached by a single legal move, together with that move, and extends the provided data structure with them.
+    ///
+    /// For every generated pair `(predecessor, mv)`, `mv` is legal in `predecessor`, and playing it produces a position equal to `self`.
+    fn generate_unmoves<E: Extend<(Self, Self::Move)>>(&self, predecessors: &mut E);
+}
+
+/// A game-playing engine, which chooses moves in positions of type `P`.
+pub trait Engine<P: Position> {
+    /// Called before each new game, so that the engine can clear any state kept from the previous game.
+    fn new_game(&mut self) {}
+
+    /// Chooses a move for the side to move. Only called in positions where the game is not decided, and there is at least one legal move.
+    ///
+    /// `time_left` is the side to move's remaining time, and `increment` is the time added to its clock after the move.
+    fn choose_move(&mut self, position: &P, time_left: Duration, increment: Duration) -> P::Move;
+}
+
+/// A game position that is cheap to copy, and takes moves back by restoring a copy instead of with a reverse move.
+///
+/// Wrap it in `CloneUndo` to use it with everything that takes a `Position`. To implement other traits in this crate, such as `EvalPosition`, wrap `CloneUndo` in a struct of your own, and forward `Position` to it, for example with `#[derive(DelegatePosition)]`.
+/// # Examples
+/// ```rust
+/// use board_game_traits::{ClonablePosition, CloneUndo, Color, GameResult, Position};
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Nim(u32, Color);
+///
+/// impl ClonablePosition for Nim {
+///     type Move = u32;
+///     type Settings = ();
+///     fn start_position_with_settings(_: &()) -> Self { Nim(10, Color::White) }
+///     fn side_to_move(&self) -> Color { self.1 }
+///     fn generate_moves<E: Extend<u32>>(&self, moves: &mut E) { moves.extend(1..=self.0.min(3)) }
+///     fn do_move(&mut self, mv: u32) { self.0 -= mv; self.1 = !self.1 }
+///     fn game_result(&self) -> Option<GameResult> { if self.0 == 0 { Some(GameResult::win_by(!self.1)) } else { None } }
+/// }
+///
+/// let mut position = CloneUndo::<Nim>::start_position();
+/// let reverse_move = position.do_move(3);
+/// assert_eq!(position.0, Nim(7, Color::Black));
+/// position.reverse_move(reverse_move);
+/// assert_eq!(position.0, Nim(10, Color::White));
+/// ```
+pub trait ClonablePosition: Clone {
+    /// The type for moves in the game.
+    type Move: Eq + Clone + fmt::Debug;
+    /// Optional Settings when initializing the position.
+    type Settings: Default;
+
+    /// Returns the starting position for the game with the given settings.
+    fn start_position_with_settings(settings: &Self::Settings) -> Self;
+
+    /// Returns the side to move for the current position.
+    fn side_to_move(&self) -> Color;
+
+    /// Generates all legal moves for the side to move, and extends the provided data structure with them.
+    fn generate_moves<E: Extend<Self::Move>>(&self, moves: &mut E);
+
+    /// Checks if a move is legal in the current position.
+    fn move_is_legal(&self, mv: Self::Move) -> bool {
+        let mut moves = vec![];
+        self.generate_moves(&mut moves);
+        moves.contains(&mv)
+    }
+
+    /// Plays a move in the position. Taking the move back is handled by `Position`.
+    fn do_move(&mut self, mv: Self::Move);
+
+    /// Returns the result if the game is decided, otherwise returns None.
+    fn game_result(&self) -> Option<GameResult>;
+
+    /// The maximum number of plies in a game. See `Position::MAX_GAME_LENGTH`.
+    const MAX_GAME_LENGTH: Option<u32> = None;
+}
+
+/// Implements `Position` for a `ClonablePosition`, with a copy of the previous position as the `ReverseMove`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CloneUndo<P>(pub P);
+
+impl<P: ClonablePosition> Position for CloneUndo<P> {
+    type Move = P::Move;
+    type ReverseMove = P;
+    type Settings = P::Settings;
+
+    fn start_position_with_settings(settings: &Self::Settings) -> Self {
+        CloneUndo(P::start_position_with_settings(settings))
+    }
+
+    fn side_to_move(&self) -> Color {
+        self.0.side_to_move()
+    }
+
+    fn generate_moves<E: Extend<Self::Move>>(&self, moves: &mut E) {
+        self.0.generate_moves(moves)
+    }
+
+    fn move_is_legal(&self, mv: Self::Move) -> bool {
+        self.0.move_is_legal(mv)
+    }
+
+    fn do_move(&mut self, mv: Self::Move) -> Self::ReverseMove {
+        let old_position = self.0.clone();
+        self.0.do_move(mv);
+        old_position
+    }
+
+    fn reverse_move(&mut self, old_position: Self::ReverseMove) {
+        self.0 = old_position;
+    }
+
+    fn game_result(&self) -> Option<GameResult> {
+        self.0.game_result()
+    }
+
+    const MAX_GAME_LENGTH: Option<u32> = P::MAX_GAME_LENGTH;
+}
+
+/// A game position that can swap the colors of the two players.
+/// Enables testing evaluation symmetry, and augmenting training data with color-swapped positions.
+pub trait ColorSwap: Position {
+    /// Returns the position with the colors of every piece and the side to move swapped.
+    /// In games where the two sides move in different directions, such as chess, the board is also flipped, so that the swapped position is equivalent to the original with the colors reversed.
+    ///
+    /// Swapping twice always returns a position equal to the original.
+    fn color_swapped(&self) -> Self;
+
+    /// Returns the move in the color-swapped position that corresponds to `mv` in the original position.
+    fn color_swap_move(mv: &Self::Move) -> Self::Move;
+}
+
+/// A game position with board symmetries, such as rotations and reflections, that can be reduced to a canonical form.
+/// Enables transposition tables, solvers and tablebases to treat all symmetric positions as one.
+///
+/// The symmetries must not change the colors of the pieces or the side to move, so symmetric positions always have the same game-theoretic value and evaluation.
+pub trait SymmetricPosition: HashablePosition {
+    /// Identifies a symmetry of the board, such as a rotation or reflection.
+    type Symmetry: Copy;
+
+    /// Returns the canonical form of the position, and the symmetry that transforms this position into it.
+    /// All symmetric positions have the same canonical form.
+    fn canonicalize(&self) -> (Self, Self::Symmetry);
+
+    /// Returns the hash of the canonical form. All symmetric positions have the same canonical hash.
+    fn canonical_hash(&self) -> Self::HashPosition {
+        self.canonicalize().0.hash_position()
+    }
+
+    /// Maps a move in this position to the corresponding move in the canonical form, given the symmetry returned by `canonicalize`.
+    fn map_move_to_canonical(&self, mv: &Self::Move, symmetry: Self::Symmetry) -> Self::Move;
+
+    /// Maps a move in the canonical form back to the corresponding move in this position, given the symmetry returned by `canonicalize`.
+    fn map_move_from_canonical(&self, mv: &Self::Move, symmetry: Self::Symmetry) -> Self::Move;
+}
+
+/// The broad category of a `ValidationError`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ValidationErrorKind {
+    /// A side has too many or too few pieces of some kind.
+    PieceCount,
+    /// A piece is on a square where it can never be, such as a pawn on the first rank in chess.
+    Placement,
+    /// The side to move is inconsistent with the rest of the position, such as the side not to move being in check.
+    SideToMove,
+    /// Some other state, such as castling rights, a move counter or a ko point, is inconsistent with the board.
+    State,
+    /// The position is internally consistent, but cannot arise from the start position through legal moves.
+    Unreachable,
+    Other,
+}
+
+/// A single inconsistency found by `ValidatePosition::validate`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ValidationError {
+    pub kind: ValidationErrorKind,
+    /// A human-readable description of the problem.
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(kind: ValidationErrorKind, message: &str) -> Self {
+        ValidationError {
+            kind,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "Invalid position ({:?}): {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// A game position that can check itself for internal consistency.
+/// Enables tools to reject positions from untrusted input, such as notation or network messages, before searching them.
+pub trait ValidatePosition: Position {
+    /// Checks that the position is consistent enough to be searched safely, and returns every problem found.
+    ///
+    /// Move generation, `do_move` and `reverse_move` must behave correctly for every position that passes validation.
+    fn validate(&self) -> Result<(), Vec<ValidationError>>;
+
+    /// Returns true if the position passes validation.
+    fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+}
+
+/// A rule that allows a player to claim a draw.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrawClaim {
+    /// The same position has occurred a certain number of times, such as threefold repetition in chess.
+    Repetition,
+    /// A certain number of moves have been played without progress, such as the fifty-move rule in chess.
+    MoveCounter,
+    Other,
+}
+
+/// A game position where players may claim a draw, as opposed to the game ending in a draw automatically.
+///
+/// Claimable draws must not be reported by `game_result`, since a player may choose to play on. The arena and game records report claimed draws separately.
+pub trait ClaimableDraws: Position {
+    /// Returns every draw claim the side to move can make in this position.
+    fn available_draw_claims(&self) -> Vec<DrawClaim>;
+
+    /// Makes a draw claim for the side to move. Returns `GameResult::Draw` if the claim is valid, or None if it is not.
+    fn claim_draw(&self, claim: DrawClaim) -> Option<GameResult> {
+        if self.available_draw_claims().contains(&claim) {
+            Some(GameResult::Draw)
+        } else {
+            None
+        }
+    }
+}
+
+/// A game position that counts the plies since the last irreversible move, such as a capture or a pawn move in chess.
+/// Enables game-agnostic tools to detect repetitions and adjudicate games without progress.
+pub trait ProgressCounter: Position {
+    /// Returns the number of plies played since the last irreversible move, or since the start of the game.
+    fn halfmove_clock(&self) -> u32;
+
+    /// Returns true if the move is irreversible, so that no position before it can occur again after it. Playing it resets the halfmove clock.
+    fn is_irreversible(&self, mv: &Self::Move) -> bool;
+}
+
+/// A game position that can tell how far the game has progressed, from the opening to the endgame.
+/// Enables tapered evaluation, where parameters are interpolated between an opening and an endgame value, and lets time management spend its time where it matters most.
+pub trait GamePhase: Position {
+    /// Returns the game phase, from 0.0 in the opening to 1.0 in the endgame.
+    fn game_phase(&self) -> f32;
+
+    /// Interpolates between an opening and an endgame value, according to the game phase.
+    /// # Examples
+    /// ```rust
+    /// use board_game_traits::{GamePhase, Position};
+    /// # use board_game_traits::{Color, GameResult};
+    /// # #[derive(Clone)]
+    /// # struct Nim(u32);
+    /// # impl Position for Nim {
+    /// #     type Move = u32;
+    /// #     type ReverseMove = u32;
+    /// #     type Settings = ();
+    /// #     fn start_position_with_settings(_: &()) -> Self { Nim(20) }
+    /// #     fn side_to_move(&self) -> Color { Color::White }
+    /// #     fn generate_moves<E: Extend<u32>>(&self, moves: &mut E) { moves.extend(1..=self.0.min(3)) }
+    /// #     fn do_move(&mut self, mv: u32) -> u32 { self.0 -= mv; mv }
+    /// #     fn reverse_move(&mut self, mv: u32) { self.0 += mv }
+    /// #     fn game_result(&self) -> Option<GameResult> { None }
+    /// # }
+    /// impl GamePhase for Nim {
+    ///     fn game_phase(&self) -> f32 {
+    ///         1.0 - self.0 as f32 / 20.0
+    ///     }
+    /// }
+    ///
+    /// let mut position = Nim::start_position();
+    /// assert_eq!(position.taper(10.0, 30.0), 10.0);
+    /// position.do_move(10);
+    /// assert_eq!(position.taper(10.0, 30.0), 20.0);
+    /// ```
+    fn taper(&self, opening: f32, endgame: f32) -> f32 {
+        let phase = self.game_phase().clamp(0.0, 1.0);
+        opening * (1.0 - phase) + endgame * phase
+    }
+}
+
+/// A game position that can detect when the side to move is under an immediate threat, such as being in check in chess, or in atari in go.
+/// Enables search algorithms to extend forcing lines, and to avoid null move pruning where passing would be unsound.
+pub trait ThreatPosition: Position {
+    /// Returns true if the side to move faces a threat that it must respond to immediately, for example being in check, or the opponent threatening a forced win.
+    ///
+    /// Search algorithms typically extend the search by a ply in such positions, and never try a null move in them.
+    fn side_to_move_is_threatened(&self) -> bool;
+}
+
+/// A game position that can generate only the moves matching a filter, such as all moves to a given square.
+/// Enables notation parsers to disambiguate moves, and GUIs to validate premoves, without generating every legal move.
+pub trait FilteredMoveGen: Position {
+    /// A description of a subset of moves, such as a destination square, an origin square, or both.
+    type MoveFilter;
+
+    /// Returns true if the move matches the filter.
+    fn move_matches(&self, mv: &Self::Move, filter: &Self::MoveFilter) -> bool;
+
+    /// Generates all legal moves that match the filter, and extends the provided data structure with them.
+    /// The default generates every legal move, and keeps the matching ones. Implementations can override it with a faster, targeted generator.
+    fn generate_moves_matching<E: Extend<Self::Move>>(
+        &self,
+        filter: &Self::MoveFilter,
+        moves: &mut E,
+    ) {
+        let mut all_moves = vec![];
+        self.generate_moves(&mut all_moves);
+        moves.extend(
+            all_moves
+                .into_iter()
+                .filter(|mv| self.move_matches(mv, filter)),
+        );
+    }
+}
+
+/// How `DisplayBoard` renders a board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BoardStyle {
+    /// Use Unicode symbols for pieces, such as ♔, instead of ASCII letters.
+    pub unicode: bool,
+    /// Label the rows and columns with coordinates.
+    pub coordinates: bool,
+    /// Show the board from black's side.
+    pub flipped: bool,
+}
+
+impl Default for BoardStyle {
+    /// Returns an ASCII style with coordinates, from white's side.
+    fn default() -> Self {
+        BoardStyle {
+            unicode: false,
+            coordinates: true,
+            flipped: false,
+        }
+    }
+}
+
+impl BoardStyle {
+    /// Returns the default style, seen from the given side.
+    pub fn from_side(color: Color) -> Self {
+        BoardStyle {
+            flipped: color == Black,
+            ..BoardStyle::default()
+        }
+    }
+}
+
+/// A game position that can be rendered as a human-readable board, for debugging and command-line tools.
+pub trait DisplayBoard: Position {
+    /// Renders the board as a multi-line string in the given style.
+    fn render(&self, style: BoardStyle) -> String;
+}
+
+/// A game position on a board of cells, with enough information about its moves for a generic GUI to map clicks to moves.
+pub trait BoardGeometry: Position {
+    /// The type for a cell on the board.
+    type Cell: Copy + Eq;
+    /// The type for the contents of a non-empty cell.
+    type Occupant;
+
+    /// Returns every cell on the board.
+    fn cells(&self) -> Vec<Self::Cell>;
+
+    /// Returns the cell's column and row for drawing the board, with (0, 0) in the top left corner from white's side.
+    /// Boards that are not rectangular, such as hexagonal boards, can use any layout that places neighbouring cells next to each other.
+    fn coordinates(&self, cell: Self::Cell) -> (i32, i32);
+
+    /// Returns the contents of the cell, or None if it is empty.
+    fn occupant(&self, cell: Self::Cell) -> Option<Self::Occupant>;
+
+    /// Returns the cell the move starts from, or None if the move does not start from a cell, like placing a stone in go.
+    fn move_origin(&self, mv: &Self::Move) -> Option<Self::Cell>;
+
+    /// Returns the cell the move ends on, or None if the move does not end on a cell, like passing.
+    fn move_destination(&self, mv: &Self::Move) -> Option<Self::Cell>;
+
+    /// Generates all legal moves starting from the cell, and extends the provided data structure with them.
+    fn moves_from_cell<E: Extend<Self::Move>>(&self, cell: Self::Cell, moves: &mut E) {
+        let mut all_moves = vec![];
+        self.generate_moves(&mut all_moves);
+        moves.extend(
+            all_moves
+                .into_iter()
+                .filter(|mv| self.move_origin(mv) == Some(cell)),
+        );
+    }
+
+    /// Generates all legal moves ending on the cell, and extends the provided data structure with them.
+    fn moves_to_cell<E: Extend<Self::Move>>(&self, cell: Self::Cell, moves: &mut E) {
+        let mut all_moves = vec![];
+        self.generate_moves(&mut all_moves);
+        moves.extend(
+            all_moves
+                .into_iter()
+                .filter(|mv| self.move_destination(mv) == Some(cell)),
+        );
+    }
+}
+
+/// A game position that knows the moves that led to it.
+/// Enables heuristics that depend on the previous move, such as countermoves and recapture extensions, and lets GUIs highlight the last move.
+///
+/// Positions that do not store their history can be wrapped in `history::PositionWithHistory`.
+pub trait HistoryAware: Position {
+    /// Returns the last move played, or None if no moves have been played.
+    fn last_move(&self) -> Option<&Self::Move>;
+
+    /// Returns the number of plies played since the start of the game.
+    fn ply(&self) -> usize;
+
+    /// Returns the move number, as written in game records. Starts at 1, and increases after every second ply.
+    fn move_number(&self) -> usize {
+        self.ply() / 2 + 1
+    }
+}
+
+/// The error returned when a position cannot be set up, with every problem found.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SetupError {
+    pub errors: Vec<ValidationError>,
+}
+
+impl From<Vec<ValidationError>> for SetupError {
+    fn from(errors: Vec<ValidationError>) -> Self {
+        SetupError { errors }
+    }
+}
+
+impl From<ValidationError> for SetupError {
+    fn from(error: ValidationError) -> Self {
+        SetupError {
+            errors: vec![error],
+        }
+    }
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "Cannot set up position")?;
+        for (i, error) in self.errors.iter().enumerate() {
+            write!(fmt, "{} {}", if i == 0 { ":" } else { ";" }, error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+/// A game position that can be constructed directly, rather than only by playing moves from the start position.
+/// Enables analysis tools, tablebase probes and test suites to work with arbitrary positions through the trait layer.
+/// # Examples
+/// ```rust
+/// use board_game_traits::{Position, SetupError, SetupPosition, ValidationError, ValidationErrorKind};
+/// # use board_game_traits::{Color, GameResult};
+/// # #[derive(Debug)]
+/// # struct Nim(u32, Color);
+/// # impl Position for Nim {
+/// #     type Move = u32;
+/// #     type ReverseMove = u32;
+/// #     type Settings = ();
+/// #     fn start_position_with_settings(_: &()) -> Self { Nim(10, Color::White) }
+/// #     fn side_to_move(&self) -> Color { self.1 }
+/// #     fn generate_moves<E: Extend<u32>>(&self, moves: &mut E) { moves.extend(1..=self.0.min(3)) }
+/// #     fn do_move(&mut self, mv: u32) -> u32 { self.0 -= mv; self.1 = !self.1; mv }
+/// #     fn reverse_move(&mut self, mv: u32) { self.0 += mv; self.1 = !self.1 }
+/// #     fn game_result(&self) -> Option<GameResult> { if self.0 == 0 { Some(GameResult::win_by(!self.1)) } else { None } }
+/// # }
+/// impl SetupPosition for Nim {
+///     type Setup = (u32, Color);
+///
+///     fn from_setup(&(stones, side_to_move): &(u32, Color), _: &()) -> Result<Self, SetupError> {
+///         if stones > 10 {
+///             return Err(ValidationError::new(ValidationErrorKind::Unreachable, "Too many stones").into());
+///         }
+///         Ok(Nim(stones, side_to_move))
+///     }
+///
+///     fn to_setup(&self) -> (u32, Color) {
+///         (self.0, self.1)
+///     }
+/// }
+///
+/// let position = Nim::from_setup(&(4, Color::Black), &()).unwrap();
+/// assert_eq!(position.to_setup(), (4, Color::Black));
+/// assert!(Nim::from_setup(&(11, Color::White), &()).is_err());
+/// ```
+pub trait SetupPosition: Position {
+    /// A description of an arbitrary position, such as a FEN string for chess or a list of stones for go.
+    type Setup;
+
+    /// Creates the described position, with the given settings.
+    ///
+    /// Must reject any setup that the position's move generation, `do_move` or `reverse_move` cannot handle correctly. Games that implement `ValidatePosition` should validate the position before returning it.
+    fn from_setup(setup: &Self::Setup, settings: &Self::Settings) -> Result<Self, SetupError>;
+
+    /// Describes the position, so that `from_setup` recreates it.
+    fn to_setup(&self) -> Self::Setup;
+}
+
+/// A game position where passing is a real, legal move, as in go.
+///
+/// Unlike the null move of `NullMovePosition`, which only exists inside a search, a pass is part of the game record, and enough consecutive passes end the game.
+/// Rollouts and the arena use this trait to end games on consecutive passes, even if `game_result` does not detect them itself.
+pub trait PassablePosition: Position {
+    /// The number of consecutive passes that ends the game.
+    const PASSES_TO_END: u32 = 2;
+
+    /// Returns the pass move.
+    fn pass_move() -> Self::Move;
+
+    /// Returns true if the side to move is allowed to pass.
+    fn can_pass(&self) -> bool;
+
+    /// Returns true if the move is a pass.
+    fn is_pass(mv: &Self::Move) -> bool {
+        *mv == Self::pass_move()
+    }
+
+    /// Returns the number of passes played in a row just before this position.
+    fn consecutive_passes(&self) -> u32;
+
+    /// Returns the result of a game ended by consecutive passes in this position, for example by counting territory in go.
+    fn score_after_passes(&self) -> GameResult;
+
+    /// Returns the result if the game has been ended by consecutive passes, otherwise returns None.
+    fn passes_result(&self) -> Option<GameResult> {
+        if self.consecutive_passes() >= Self::PASSES_TO_END {
+            Some(self.score_after_passes())
+        } else {
+            None
+        }
+    }
 }
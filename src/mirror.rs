@@ -0,0 +1,148 @@
+//! Viewing positions with the colors swapped, and checking that evaluation functions are symmetric.
+
+use crate::{Color, ColorSwap, EvalPosition, GameResult, Position, PruningHints};
+#[cfg(feature = "rand")]
+use rand::Rng;
+
+/// Presents a position as its color-swapped equivalent, without swapping the underlying position.
+///
+/// Moves, the side to move, results and evaluations are all translated on the fly, so the wrapper behaves exactly like `color_swapped` would, as long as the game's `ColorSwap` implementation is correct.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MirroredPosition<P>(pub P);
+
+impl<P: ColorSwap> MirroredPosition<P> {
+    /// Returns the presented position as a normal position, by swapping the underlying position's colors.
+    pub fn to_swapped(&self) -> P {
+        self.0.color_swapped()
+    }
+}
+
+impl<P: ColorSwap> Position for MirroredPosition<P> {
+    type Move = P::Move;
+    type ReverseMove = P::ReverseMove;
+    type Settings = P::Settings;
+
+    fn start_position_with_settings(settings: &Self::Settings) -> Self {
+        MirroredPosition(P::start_position_with_settings(settings))
+    }
+
+    fn side_to_move(&self) -> Color {
+        !self.0.side_to_move()
+    }
+
+    fn generate_moves<E: Extend<Self::Move>>(&self, moves: &mut E) {
+        let mut inner_moves = vec![];
+        self.0.generate_moves(&mut inner_moves);
+        moves.extend(inner_moves.iter().map(P::color_swap_move));
+    }
+
+    fn move_is_legal(&self, mv: Self::Move) -> bool {
+        self.0.move_is_legal(P::color_swap_move(&mv))
+    }
+
+    fn do_move(&mut self, mv: Self::Move) -> Self::ReverseMove {
+        self.0.do_move(P::color_swap_move(&mv))
+    }
+
+    fn reverse_move(&mut self, mv: Self::ReverseMove) {
+        self.0.reverse_move(mv)
+    }
+
+    fn game_result(&self) -> Option<GameResult> {
+        self.0.game_result().map(|result| !result)
+    }
+
+    const MAX_GAME_LENGTH: Option<u32> = P::MAX_GAME_LENGTH;
+}
+
+impl<P: ColorSwap + EvalPosition> EvalPosition for MirroredPosition<P> {
+    fn static_eval(&self) -> f32 {
+        -self.0.static_eval()
+    }
+
+    fn pruning_profile(&self) -> PruningHints {
+        self.0.pruning_profile()
+    }
+
+    const BRANCH_FACTOR: u64 = P::BRANCH_FACTOR;
+}
+
+/// Plays `num_games` random games of at most `max_plies` plies from the start position, and checks every position along the way:
+/// * Swapping colors twice returns the original position.
+/// * The swapped position has the other side to move, the swapped result, and the swapped moves.
+/// * The static evaluation of the swapped position is the negated evaluation of the original, within `tolerance`.
+///
+/// # Panics
+///
+/// Panics if any check fails.
+#[cfg(feature = "rand")]
+pub fn assert_eval_symmetry<P, R>(rng: &mut R, num_games: usize, max_plies: u32, tolerance: f32)
+where
+    P: ColorSwap + EvalPosition,
+    R: Rng,
+{
+    for game in 0..num_games {
+        let mut position = P::start_position();
+        for ply in 0..=max_plies {
+            let swapped = position.color_swapped();
+            assert!(
+                swapped.color_swapped() == position,
+                "Swapping colors twice changed the position in game {}, ply {}",
+                game,
+                ply
+            );
+            assert_eq!(
+                swapped.side_to_move(),
+                !position.side_to_move(),
+                "Wrong side to move after swapping colors in game {}, ply {}",
+                game,
+                ply
+            );
+            assert_eq!(
+                swapped.game_result(),
+                position.game_result().map(|result| !result),
+                "Wrong result after swapping colors in game {}, ply {}",
+                game,
+                ply
+            );
+            // Swapping colors also swaps the side to move, so the relative evaluations should be equal
+            let eval = position.eval_relative();
+            let swapped_eval = swapped.eval_relative();
+            assert!(
+                (eval - swapped_eval).abs() <= tolerance,
+                "Evaluation is not symmetric in game {}, ply {}: {} for the side to move, {} after swapping colors",
+                game,
+                ply,
+                eval,
+                swapped_eval
+            );
+
+            let mut moves = vec![];
+            position.generate_moves(&mut moves);
+            let mut swapped_moves = vec![];
+            swapped.generate_moves(&mut swapped_moves);
+            assert_eq!(
+                swapped_moves.len(),
+                moves.len(),
+                "Wrong number of moves after swapping colors in game {}, ply {}",
+                game,
+                ply
+            );
+            for mv in moves.iter() {
+                assert!(
+                    swapped_moves.contains(&P::color_swap_move(mv)),
+                    "Move {:?} has no color-swapped equivalent in game {}, ply {}",
+                    mv,
+                    game,
+                    ply
+                );
+            }
+
+            if moves.is_empty() || position.game_result().is_some() {
+                break;
+            }
+            let mv = moves.swap_remove(rng.gen_range(0..moves.len()));
+            position.do_move(mv);
+        }
+    }
+}
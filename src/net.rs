@@ -0,0 +1,361 @@
+//! Playing games over the network, through a relay server. Available with the `net` feature.
+//!
+//! Clients connect to the server over TCP, and seek a game. The server pairs seekers in the order they arrive, the first as white, and relays moves between them.
+//! The server checks every move for legality, and keeps both clocks, so clients never have to trust each other.
+//!
+//! Each message is a big-endian `u32` length, followed by that many bytes of JSON. Moves are sent in their serde representation.
+//! Clients may only send messages on their own turn, except for the initial seek. The server answers every legal move by sending it to both clients, together with both clocks.
+
+use crate::clock::{SystemTimeSource, TimeControl, TimeSource, TimedGame};
+use crate::game::{GameEndReason, GameOutcome};
+use crate::{Color, Engine, GameResult, Position};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// The largest message the protocol accepts, in bytes.
+pub const MAX_MESSAGE_SIZE: u32 = 1 << 20;
+
+/// How long the server waits for a new connection to seek a game.
+const SEEK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A message from a client to the server.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClientMessage<M> {
+    /// Asks to be paired with the next client that seeks a game.
+    Seek {
+        name: String,
+    },
+    /// Plays a move.
+    Move(M),
+    Resign,
+}
+
+/// A message from the server to a client.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServerMessage<M> {
+    /// The client has been paired with an opponent, and the game has started from the server's start position.
+    GameStarted {
+        color: Color,
+        opponent: String,
+        time_control: TimeControl,
+    },
+    /// A move was played, by either side. `clocks` holds white's and black's time left after the move.
+    Move {
+        mv: M,
+        clocks: [Duration; 2],
+    },
+    GameOver(GameOutcome),
+}
+
+/// Writes a length-prefixed message.
+pub fn write_message<W: Write, T: Serialize>(writer: &mut W, message: &T) -> io::Result<()> {
+    let bytes = serde_json::to_vec(message)?;
+    if bytes.len() > MAX_MESSAGE_SIZE as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Message is too large",
+        ));
+    }
+    writer.write_all(&(bytes.len() as u32).to_be_bytes())?;
+    writer.write_all(&bytes)?;
+    writer.flush()
+}
+
+/// Reads a length-prefixed message.
+pub fn read_message<R: Read, T: DeserializeOwned>(reader: &mut R) -> io::Result<T> {
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length);
+    if length > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Message is too large",
+        ));
+    }
+    let mut bytes = vec![0; length as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// A relay server that pairs clients, and referees their games.
+/// # Examples
+/// ```rust
+/// use board_game_traits::clock::TimeControl;
+/// use board_game_traits::net::{Client, Server};
+/// use board_game_traits::{Engine, GameResult, Position};
+/// use std::net::TcpListener;
+/// use std::thread;
+/// use std::time::Duration;
+/// # use board_game_traits::Color;
+/// # use board_game_traits::testing::Nim;
+/// # struct TakeOne;
+/// # impl Engine<Nim> for TakeOne {
+/// #     fn choose_move(&mut self, _: &Nim, _: Duration, _: Duration) -> u32 { 1 }
+/// # }
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let address = listener.local_addr().unwrap();
+/// let server = Server::new(Nim::start_position(), TimeControl::new(Duration::from_secs(10), Duration::ZERO));
+/// thread::spawn(move || server.run(&listener));
+///
+/// let players: Vec<_> = ["alice", "bob"]
+///     .iter()
+///     .map(|name| {
+///         thread::spawn(move || {
+///             let mut client = Client::connect(address).unwrap();
+///             client.seek(name).unwrap();
+///             client.play(Nim::start_position(), &mut TakeOne).unwrap()
+///         })
+///     })
+///     .collect();
+/// for player in players {
+///     // Taking one stone at a time from 10, black takes the last one
+///     assert_eq!(player.join().unwrap().result, GameResult::BlackWin);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Server<P, T = SystemTimeSource> {
+    start_position: P,
+    time_control: TimeControl,
+    time_source: T,
+}
+
+impl<P> Server<P>
+where
+    P: Position + Clone + Sync,
+    P::Move: Serialize + DeserializeOwned,
+{
+    pub fn new(start_position: P, time_control: TimeControl) -> Self {
+        Server::with_time_source(start_position, time_control, SystemTimeSource::default())
+    }
+}
+
+impl<P, T> Server<P, T>
+where
+    P: Position + Clone + Sync,
+    P::Move: Serialize + DeserializeOwned,
+    T: TimeSource + Sync,
+{
+    /// Creates a server that charges the clients' thinking time with `time_source`, for example a `ManualTimeSource` in tests.
+    ///
+    /// Sockets still time out in real time, after the time left on the client's clock.
+    pub fn with_time_source(start_position: P, time_control: TimeControl, time_source: T) -> Self {
+        Server {
+            start_position,
+            time_control,
+            time_source,
+        }
+    }
+
+    /// Accepts connections, and plays a game for every pair of seeking clients, each on its own thread.
+    /// Only returns if accepting a connection fails.
+    pub fn run(&self, listener: &TcpListener) -> io::Result<()> {
+        thread::scope(|scope| {
+            let (seekers, new_seekers) = mpsc::channel();
+            scope.spawn(move || {
+                while let (Ok(white), Ok(black)) = (new_seekers.recv(), new_seekers.recv()) {
+                    scope.spawn(move || self.play_game(white, black));
+                }
+            });
+            for stream in listener.incoming() {
+                let mut stream = stream?;
+                let seekers = seekers.clone();
+                scope.spawn(move || {
+                    stream.set_read_timeout(Some(SEEK_TIMEOUT))?;
+                    if let ClientMessage::<P::Move>::Seek { name } = read_message(&mut stream)? {
+                        // The matcher only stops once this function returns
+                        let _ = seekers.send((stream, name));
+                    }
+                    Ok::<_, io::Error>(())
+                });
+            }
+            Ok(())
+        })
+    }
+
+    /// Plays a game between two clients that have already sought a game, given with their names.
+    /// Returns the outcome, which is also sent to both clients.
+    pub fn play_game(&self, white: (TcpStream, String), black: (TcpStream, String)) -> GameOutcome {
+        let (mut white, white_name) = white;
+        let (mut black, black_name) = black;
+        let mut game = TimedGame::new(self.start_position.clone(), self.time_control);
+
+        let started = self.send_game_started(&mut white, Color::White, black_name)
+            && self.send_game_started(&mut black, Color::Black, white_name);
+        let outcome = if started {
+            self.referee(&mut game, [&mut white, &mut black])
+        } else {
+            GameOutcome {
+                result: GameResult::Draw,
+                reason: GameEndReason::Disconnected,
+            }
+        };
+        for stream in [&mut white, &mut black] {
+            let _ = write_message(stream, &ServerMessage::<P::Move>::GameOver(outcome));
+        }
+        outcome
+    }
+
+    /// Returns whether the message was sent.
+    fn send_game_started(&self, stream: &mut TcpStream, color: Color, opponent: String) -> bool {
+        let message = ServerMessage::<P::Move>::GameStarted {
+            color,
+            opponent,
+            time_control: self.time_control,
+        };
+        write_message(stream, &message).is_ok()
+    }
+
+    fn referee(&self, game: &mut TimedGame<P>, mut streams: [&mut TcpStream; 2]) -> GameOutcome {
+        let mut legal_moves = vec![];
+        loop {
+            if let Some(outcome) = game.outcome() {
+                return outcome;
+            }
+            legal_moves.clear();
+            game.position().generate_moves(&mut legal_moves);
+            if legal_moves.is_empty() {
+                return GameOutcome {
+                    result: GameResult::Draw,
+                    reason: GameEndReason::NoLegalMoves,
+                };
+            }
+
+            let side_to_move = game.position().side_to_move();
+            let forfeit = |reason| GameOutcome {
+                result: GameResult::win_by(!side_to_move),
+                reason,
+            };
+            let stream = &mut *streams[side_to_move.disc()];
+            let deadline = game.time_left(side_to_move) + self.time_control.delay;
+            // A zero timeout means no timeout, so always wait a little
+            if stream
+                .set_read_timeout(Some(deadline.max(Duration::from_millis(1))))
+                .is_err()
+            {
+                return forfeit(GameEndReason::Disconnected);
+            }
+            let start_time = self.time_source.now();
+            let message = read_message::<_, ClientMessage<P::Move>>(stream);
+            let time_used = self.time_source.now().saturating_sub(start_time);
+            if let Some(outcome) = game.check_flag(time_used) {
+                return outcome;
+            }
+            let mv = match message {
+                Ok(ClientMessage::Move(mv)) => mv,
+                Ok(ClientMessage::Resign) => return forfeit(GameEndReason::Resignation),
+                Ok(ClientMessage::Seek { .. }) => return forfeit(GameEndReason::Disconnected),
+                Err(err)
+                    if err.kind() == io::ErrorKind::WouldBlock
+                        || err.kind() == io::ErrorKind::TimedOut =>
+                {
+                    return forfeit(GameEndReason::Timeout)
+                }
+                Err(_) => return forfeit(GameEndReason::Disconnected),
+            };
+            if !legal_moves.contains(&mv) {
+                return forfeit(GameEndReason::IllegalMove);
+            }
+            game.play_move(mv.clone(), time_used);
+
+            let message = ServerMessage::Move {
+                mv,
+                clocks: [game.time_left(Color::White), game.time_left(Color::Black)],
+            };
+            for (color, stream) in Color::ALL.iter().zip(streams.iter_mut()) {
+                if write_message(&mut **stream, &message).is_err() {
+                    return GameOutcome {
+                        result: GameResult::win_by(!*color),
+                        reason: GameEndReason::Disconnected,
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// A connection to a relay server.
+#[derive(Debug)]
+pub struct Client<M> {
+    stream: TcpStream,
+    phantom: PhantomData<fn(M) -> M>,
+}
+
+impl<M: Serialize + DeserializeOwned> Client<M> {
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        Ok(Client {
+            stream: TcpStream::connect(address)?,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Asks the server for a game. The server answers with `ServerMessage::GameStarted` once an opponent is found.
+    pub fn seek(&mut self, name: &str) -> io::Result<()> {
+        self.send(&ClientMessage::Seek {
+            name: name.to_string(),
+        })
+    }
+
+    pub fn send_move(&mut self, mv: M) -> io::Result<()> {
+        self.send(&ClientMessage::Move(mv))
+    }
+
+    pub fn resign(&mut self) -> io::Result<()> {
+        self.send(&ClientMessage::Resign)
+    }
+
+    /// Waits for the next message from the server.
+    pub fn receive(&mut self) -> io::Result<ServerMessage<M>> {
+        read_message(&mut self.stream)
+    }
+
+    fn send(&mut self, message: &ClientMessage<M>) -> io::Result<()> {
+        write_message(&mut self.stream, message)
+    }
+
+    /// Plays a game with the engine, after seeking one. `start_position` must be the same as the server's.
+    /// Returns the outcome once the server reports that the game is over.
+    pub fn play<P, E>(&mut self, mut position: P, engine: &mut E) -> io::Result<GameOutcome>
+    where
+        P: Position<Move = M>,
+        E: Engine<P> + ?Sized,
+    {
+        let (color, time_control) = loop {
+            match self.receive()? {
+                ServerMessage::GameStarted {
+                    color,
+                    time_control,
+                    ..
+                } => break (color, time_control),
+                ServerMessage::GameOver(outcome) => return Ok(outcome),
+                ServerMessage::Move { .. } => {}
+            }
+        };
+        engine.new_game();
+        let mut time_left = time_control.time;
+        loop {
+            if position.side_to_move() == color && position.game_result().is_none() {
+                let mv = engine.choose_move(&position, time_left, time_control.increment);
+                self.send_move(mv)?;
+            }
+            match self.receive()? {
+                ServerMessage::Move { mv, clocks } => {
+                    position.do_move(mv);
+                    time_left = clocks[color.disc()];
+                }
+                ServerMessage::GameOver(outcome) => return Ok(outcome),
+                ServerMessage::GameStarted { .. } => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Unexpected GameStarted message during a game",
+                    ))
+                }
+            }
+        }
+    }
+}
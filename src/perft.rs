@@ -0,0 +1,197 @@
+//! Perft: counting the leaf nodes of the game tree to a fixed depth, for testing move generation.
+//!
+//! Comparing perft counts against known values, or against a different implementation, is the standard way to find move generation bugs.
+//! `perft_parallel`, which splits the root moves across threads, is available with the `rayon` feature.
+//!
+//! The functions take any `Position2`, which includes every `Position`.
+
+use crate::v2::Position2;
+
+/// Returns the number of leaf nodes in the game tree `depth` plies below the position. As in the standard definition, only positions exactly `depth` plies down are counted: games that are decided earlier contribute nothing, even if `generate_moves` would still return moves.
+///
+/// The position is restored to its original state before returning.
+pub fn perft<P: Position2>(position: &mut P, depth: u16) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    if position.game_result().is_some() {
+        return 0;
+    }
+    if depth == 1 {
+        return position.legal_moves().count() as u64;
+    }
+    let moves: Vec<P::Move> = position.legal_moves().collect();
+    let mut nodes = 0;
+    for mv in moves {
+        let reverse_move = position.do_move(mv);
+        nodes += perft(position, depth - 1);
+        position.reverse_move(reverse_move);
+    }
+    nodes
+}
+
+/// Returns the perft count below each legal move, in move generation order. Useful for narrowing down which move leads to a wrong count.
+///
+/// # Panics
+///
+/// Panics if `depth` is zero.
+pub fn perft_divide<P: Position2>(position: &mut P, depth: u16) -> Vec<(P::Move, u64)> {
+    assert!(depth > 0, "Cannot divide perft at depth 0");
+    let mut moves = vec![];
+    if position.game_result().is_none() {
+        position.generate_moves(&mut moves);
+    }
+    moves
+        .into_iter()
+        .map(|mv| {
+            let reverse_move = position.do_move(mv.clone());
+            let nodes = perft(position, depth - 1);
+            position.reverse_move(reverse_move);
+            (mv, nodes)
+        })
+        .collect()
+}
+
+/// Returns the same count as `perft`, but searches the root moves in parallel, each on its own copy of the position.
+#[cfg(feature = "rayon")]
+pub fn perft_parallel<P>(position: &P, depth: u16) -> u64
+where
+    P: Position2 + Clone + Send,
+    P::Move: Send,
+{
+    use rayon::prelude::*;
+
+    if depth <= 1 || position.game_result().is_some() {
+        return perft(&mut position.clone(), depth);
+    }
+    let mut moves = vec![];
+    position.generate_moves(&mut moves);
+    moves
+        .into_par_iter()
+        .map_with(position.clone(), |position, mv| {
+            let reverse_move = position.do_move(mv);
+            let nodes = perft(position, depth - 1);
+            position.reverse_move(reverse_move);
+            nodes
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Nim;
+    use crate::Color;
+
+    #[test]
+    fn perft_counts_leaves_at_depth() {
+        let mut position = Nim::start_position();
+        let counts: Vec<u64> = (0..=5).map(|depth| perft(&mut position, depth)).collect();
+        // From depth 4, some games have already been decided by taking the last stone
+        assert_eq!(counts, [1, 3, 9, 27, 76, 147]);
+        assert_eq!(position, Nim::start_position());
+    }
+
+    #[test]
+    fn decided_games_are_not_counted_below_their_depth() {
+        // Taking both stones ends the game at depth 1, so only taking one stone twice reaches depth 2
+        let mut position = Nim(2, Color::White);
+        assert_eq!(perft(&mut position, 1), 2);
+        assert_eq!(perft(&mut position, 2), 1);
+        assert_eq!(perft(&mut position, 3), 0);
+        assert_eq!(perft(&mut Nim(0, Color::White), 0), 1);
+        assert_eq!(perft(&mut Nim(0, Color::White), 1), 0);
+    }
+
+    /// Nim implemented directly on `Position2`, with a lazy move iterator.
+    #[derive(Clone, Debug, PartialEq)]
+    struct LazyNim(u32, Color);
+
+    impl Position2 for LazyNim {
+        type Move = u32;
+        type ReverseMove = u32;
+        type Settings = ();
+        type MoveIter<'a>
+            = std::ops::RangeInclusive<u32>
+        where
+            Self: 'a;
+
+        fn start_position_with_settings(_: &()) -> Self {
+            LazyNim(10, Color::White)
+        }
+
+        fn side_to_move(&self) -> Color {
+            self.1
+        }
+
+        fn legal_moves(&self) -> Self::MoveIter<'_> {
+            1..=self.0.min(3)
+        }
+
+        fn do_move(&mut self, mv: u32) -> u32 {
+            self.0 -= mv;
+            self.1 = !self.1;
+            mv
+        }
+
+        fn reverse_move(&mut self, mv: u32) {
+            self.0 += mv;
+            self.1 = !self.1;
+        }
+
+        fn game_result(&self) -> Option<crate::GameResult> {
+            if self.0 == 0 {
+                Some(crate::GameResult::win_by(!self.1))
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn direct_position2_implementations_match_the_bridge() {
+        let mut lazy = LazyNim::start_position();
+        let mut bridged = Nim::start_position();
+        for depth in 0..=8 {
+            assert_eq!(perft(&mut lazy, depth), perft(&mut bridged, depth));
+        }
+        assert_eq!(lazy, LazyNim::start_position());
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let mut position = Nim(5, Color::White);
+        let divide = perft_divide(&mut position, 4);
+        assert_eq!(
+            divide.iter().map(|&(mv, _)| mv).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert_eq!(
+            divide.iter().map(|&(_, nodes)| nodes).sum::<u64>(),
+            perft(&mut position, 4)
+        );
+    }
+}
+
+#[cfg(all(test, feature = "rayon"))]
+mod parallel_tests {
+    use super::*;
+    use crate::testing::Nim;
+    use crate::Color;
+
+    #[test]
+    fn perft_parallel_equals_perft() {
+        for stones in 0..=12 {
+            let mut position = Nim(stones, Color::White);
+            for depth in 0..=8 {
+                assert_eq!(
+                    perft_parallel(&position, depth),
+                    perft(&mut position, depth),
+                    "{} stones, depth {}",
+                    stones,
+                    depth
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Random playouts, and static evaluation by random playouts for games without a handcrafted evaluation function.
+
+use crate::{Color, EvalPosition, GameResult, PassablePosition, Position, RolloutPosition};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use std::thread;
+
+/// Plays random moves from the position until the game is decided, and returns the result.
+/// If the game is still undecided after `max_plies` moves, or after `P::MAX_GAME_LENGTH` moves, or the side to move has no legal moves, the game is adjudicated as a draw.
+pub fn rollout<P: RolloutPosition, R: Rng>(
+    mut position: P,
+    rng: &mut R,
+    max_plies: u32,
+) -> GameResult {
+    for _ in 0..crate::ply_limit::<P>(max_plies) {
+        if let Some(result) = position.game_result() {
+            return result;
+        }
+        match position.random_move(rng) {
+            Some(mv) => {
+                position.do_move(mv);
+            }
+            None => return GameResult::Draw,
+        }
+    }
+    position.game_result().unwrap_or(GameResult::Draw)
+}
+
+/// Plays random moves from the position like `rollout`, but also ends the game once enough consecutive passes have been played, and scores it with `score_after_passes`.
+///
+/// Random moves only pass when passing is the only legal move, so that rollouts do not end early by two random passes.
+pub fn rollout_with_passes<P: RolloutPosition + PassablePosition, R: Rng>(
+    mut position: P,
+    rng: &mut R,
+    max_plies: u32,
+) -> GameResult {
+    let mut moves = vec![];
+    for _ in 0..crate::ply_limit::<P>(max_plies) {
+        if let Some(result) = position.game_result().or_else(|| position.passes_result()) {
+            return result;
+        }
+        let mv = match position.random_move(rng) {
+            Some(mv) if P::is_pass(&mv) => {
+                moves.clear();
+                position.generate_moves(&mut moves);
+                moves.retain(|mv| !P::is_pass(mv));
+                if moves.is_empty() {
+                    mv
+                } else {
+                    moves.swap_remove(rng.gen_range(0..moves.len()))
+                }
+            }
+            Some(mv) => mv,
+            None if position.can_pass() => P::pass_move(),
+            None => return GameResult::Draw,
+        };
+        position.do_move(mv);
+    }
+    position
+        .game_result()
+        .or_else(|| position.passes_result())
+        .unwrap_or(GameResult::Draw)
+}
+
+/// Wraps a position, and implements `EvalPosition` by playing random games from it with `rollout`, so that alpha-beta search can be used on games without an evaluation function.
+///
+/// The evaluation is white's expected score over the playouts, scaled to between -100 (black always wins) and 100 (white always wins). Playouts that reach `max_plies` without a result count as draws.
+/// Each playout is seeded from `seed` and its own index, so the evaluation of a position is always the same, regardless of the number of threads.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RolloutEval<P> {
+    pub position: P,
+    /// The number of random games played for each evaluation.
+    pub playouts: u32,
+    /// The maximum length of each random game.
+    pub max_plies: u32,
+    pub seed: u64,
+    /// The number of threads to play the random games on.
+    pub threads: usize,
+}
+
+impl<P> RolloutEval<P> {
+    /// Wraps the position with 100 single-threaded playouts of at most 1000 plies, and a seed of 0.
+    pub fn new(position: P) -> Self {
+        RolloutEval {
+            position,
+            playouts: 100,
+            max_plies: 1000,
+            seed: 0,
+            threads: 1,
+        }
+    }
+}
+
+impl<P: RolloutPosition + Clone + Sync> RolloutEval<P> {
+    /// Returns the sum of white's scores over the given playouts, counting wins as 1 and draws as 0.5.
+    fn white_score(&self, playout_indices: impl Iterator<Item = u32>) -> f64 {
+        playout_indices
+            .map(|i| {
+                let mut rng = SmallRng::seed_from_u64(self.seed.wrapping_add(i as u64));
+                match rollout(self.position.clone(), &mut rng, self.max_plies) {
+                    GameResult::WhiteWin => 1.0,
+                    GameResult::BlackWin => 0.0,
+                    GameResult::Draw => 0.5,
+                }
+            })
+            .sum()
+    }
+}
+
+impl<P: Position> Position for RolloutEval<P> {
+    type Move = P::Move;
+    type ReverseMove = P::ReverseMove;
+    type Settings = P::Settings;
+
+    fn start_position_with_settings(settings: &Self::Settings) -> Self {
+        RolloutEval::new(P::start_position_with_settings(settings))
+    }
+
+    fn side_to_move(&self) -> Color {
+        self.position.side_to_move()
+    }
+
+    fn generate_moves<E: Extend<Self::Move>>(&self, moves: &mut E) {
+        self.position.generate_moves(moves)
+    }
+
+    fn move_is_legal(&self, mv: Self::Move) -> bool {
+        self.position.move_is_legal(mv)
+    }
+
+    fn do_move(&mut self, mv: Self::Move) -> Self::ReverseMove {
+        self.position.do_move(mv)
+    }
+
+    fn reverse_move(&mut self, mv: Self::ReverseMove) {
+        self.position.reverse_move(mv)
+    }
+
+    fn game_result(&self) -> Option<GameResult> {
+        self.position.game_result()
+    }
+
+    const MAX_GAME_LENGTH: Option<u32> = P::MAX_GAME_LENGTH;
+}
+
+impl<P: RolloutPosition> RolloutPosition for RolloutEval<P> {
+    fn random_move<R: Rng>(&self, rng: &mut R) -> Option<Self::Move> {
+        self.position.random_move(rng)
+    }
+}
+
+impl<P: RolloutPosition + Clone + PartialEq + Sync> EvalPosition for RolloutEval<P> {
+    fn static_eval(&self) -> f32 {
+        if self.playouts == 0 {
+            return 0.0;
+        }
+        let threads = self.threads.max(1) as u32;
+        let white_score: f64 = if threads == 1 {
+            self.white_score(0..self.playouts)
+        } else {
+            thread::scope(|scope| {
+                let handles: Vec<_> = (0..threads)
+                    .map(|thread| {
+                        scope.spawn(move || {
+                            self.white_score((thread..self.playouts).step_by(threads as usize))
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap())
+                    .sum()
+            })
+        };
+        ((white_score / self.playouts as f64 * 2.0 - 1.0) * 100.0) as f32
+    }
+}
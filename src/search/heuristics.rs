@@ -0,0 +1,191 @@
+//! Move ordering heuristics that are shared by most alpha-beta searches.
+//!
+//! The tables only store and score moves. Search algorithms are responsible for updating them on beta cutoffs, and for consulting them when ordering moves.
+
+use crate::HistoryAware;
+
+/// Stores killer moves: quiet moves that recently caused a beta cutoff at the same ply, and are therefore likely to cause one in sibling nodes too.
+/// # Examples
+/// ```rust
+/// use board_game_traits::search::heuristics::KillerTable;
+/// let mut killers = KillerTable::new(2);
+/// killers.add(3, "Nf3");
+/// killers.add(3, "e4");
+/// killers.add(3, "d4");
+/// assert_eq!(killers.killers(3), ["d4", "e4"]);
+/// assert!(killers.killers(4).is_empty());
+/// ```
+#[derive(Clone, Debug)]
+pub struct KillerTable<M> {
+    slots_per_ply: usize,
+    killers: Vec<Vec<M>>,
+}
+
+impl<M> Default for KillerTable<M> {
+    /// Returns a table with two killer slots per ply.
+    fn default() -> Self {
+        KillerTable::new(2)
+    }
+}
+
+impl<M> KillerTable<M> {
+    /// Returns an empty table, which stores up to `slots_per_ply` killer moves at each ply.
+    pub fn new(slots_per_ply: usize) -> Self {
+        KillerTable {
+            slots_per_ply,
+            killers: vec![],
+        }
+    }
+
+    /// Returns the killer moves at the given ply, most recent first.
+    pub fn killers(&self, ply: usize) -> &[M] {
+        self.killers.get(ply).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Removes all killer moves.
+    pub fn clear(&mut self) {
+        self.killers.clear()
+    }
+}
+
+impl<M: Eq> KillerTable<M> {
+    /// Stores a killer move at the given ply. If the table is full at that ply, the oldest killer move is removed.
+    pub fn add(&mut self, ply: usize, mv: M) {
+        if self.slots_per_ply == 0 {
+            return;
+        }
+        if self.killers.len() <= ply {
+            self.killers.resize_with(ply + 1, Vec::new);
+        }
+        let killers = &mut self.killers[ply];
+        if let Some(position) = killers.iter().position(|killer| *killer == mv) {
+            killers.remove(position);
+        } else if killers.len() == self.slots_per_ply {
+            killers.pop();
+        }
+        killers.insert(0, mv);
+    }
+
+    /// Checks if the move is a killer move at the given ply.
+    pub fn is_killer(&self, ply: usize, mv: &M) -> bool {
+        self.killers(ply).contains(mv)
+    }
+}
+
+/// The largest absolute score in a `HistoryTable`.
+pub const MAX_HISTORY_SCORE: i32 = 1 << 14;
+
+/// Scores moves by how often they have caused beta cutoffs anywhere in the search tree, weighted by depth.
+///
+/// Moves are mapped to table entries through a user-supplied index function, for example from the move's origin and destination squares. The scores saturate towards `MAX_HISTORY_SCORE`, so recent results keep having an effect in long searches.
+#[derive(Clone, Debug)]
+pub struct HistoryTable<M> {
+    move_index: fn(&M) -> usize,
+    scores: Vec<i32>,
+}
+
+impl<M> HistoryTable<M> {
+    /// Returns an empty table. `move_index` must map every move to an index smaller than `num_indices`.
+    pub fn new(num_indices: usize, move_index: fn(&M) -> usize) -> Self {
+        HistoryTable {
+            move_index,
+            scores: vec![0; num_indices],
+        }
+    }
+
+    /// Returns the history score of the move, between `-MAX_HISTORY_SCORE` and `MAX_HISTORY_SCORE`.
+    pub fn score(&self, mv: &M) -> i32 {
+        self.scores[(self.move_index)(mv)]
+    }
+
+    /// Rewards a move that caused a beta cutoff at the given remaining depth.
+    pub fn reward(&mut self, mv: &M, depth: u16) {
+        self.update(mv, Self::bonus(depth))
+    }
+
+    /// Penalizes a move that was searched before the cutoff move at the given remaining depth, without causing a cutoff itself.
+    pub fn penalize(&mut self, mv: &M, depth: u16) {
+        self.update(mv, -Self::bonus(depth))
+    }
+
+    /// Halves all scores, so that older results count for less. Typically called between searches.
+    pub fn age(&mut self) {
+        for score in self.scores.iter_mut() {
+            *score /= 2;
+        }
+    }
+
+    /// Resets all scores to zero.
+    pub fn clear(&mut self) {
+        for score in self.scores.iter_mut() {
+            *score = 0;
+        }
+    }
+
+    fn bonus(depth: u16) -> i32 {
+        (depth as i32 * depth as i32).min(MAX_HISTORY_SCORE)
+    }
+
+    fn update(&mut self, mv: &M, bonus: i32) {
+        let score = &mut self.scores[(self.move_index)(mv)];
+        // Scale the update down as the score approaches the maximum, so that it never overflows the bounds
+        *score += bonus - *score * bonus.abs() / MAX_HISTORY_SCORE;
+    }
+}
+
+/// Stores countermoves: for each previous move, the reply that most recently caused a beta cutoff after it.
+///
+/// Previous moves are mapped to table entries through a user-supplied index function, in the same way as for `HistoryTable`.
+#[derive(Clone, Debug)]
+pub struct CounterMoveTable<M> {
+    move_index: fn(&M) -> usize,
+    counter_moves: Vec<Option<M>>,
+}
+
+impl<M> CounterMoveTable<M> {
+    /// Returns an empty table. `move_index` must map every move to an index smaller than `num_indices`.
+    pub fn new(num_indices: usize, move_index: fn(&M) -> usize) -> Self {
+        CounterMoveTable {
+            move_index,
+            counter_moves: (0..num_indices).map(|_| None).collect(),
+        }
+    }
+
+    /// Returns the stored countermove to the previous move, if any.
+    pub fn counter_move(&self, previous_move: &M) -> Option<&M> {
+        self.counter_moves[(self.move_index)(previous_move)].as_ref()
+    }
+
+    /// Stores a move that caused a beta cutoff in reply to the previous move, replacing any older countermove.
+    pub fn set(&mut self, previous_move: &M, counter_move: M) {
+        self.counter_moves[(self.move_index)(previous_move)] = Some(counter_move)
+    }
+
+    /// Removes all countermoves.
+    pub fn clear(&mut self) {
+        for counter_move in self.counter_moves.iter_mut() {
+            *counter_move = None;
+        }
+    }
+}
+
+impl<M: Eq> CounterMoveTable<M> {
+    /// Checks if the move is the stored countermove to the previous move.
+    pub fn is_counter_move(&self, previous_move: &M, mv: &M) -> bool {
+        self.counter_move(previous_move) == Some(mv)
+    }
+}
+
+impl<M> CounterMoveTable<M> {
+    /// Returns the stored countermove to the last move played in the position, if any.
+    pub fn counter_move_in<P: HistoryAware<Move = M>>(&self, position: &P) -> Option<&M> {
+        self.counter_move(position.last_move()?)
+    }
+
+    /// Stores a move that caused a beta cutoff in the position, as the countermove to the last move played. Does nothing if no moves have been played.
+    pub fn set_in<P: HistoryAware<Move = M>>(&mut self, position: &P, counter_move: M) {
+        if let Some(previous_move) = position.last_move() {
+            self.set(previous_move, counter_move)
+        }
+    }
+}
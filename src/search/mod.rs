@@ -0,0 +1,7 @@
+//! Game-agnostic building blocks for search algorithms.
+
+pub mod heuristics;
+#[cfg(feature = "shared-tt")]
+pub mod shared_transposition_table;
+pub mod stats;
+pub mod transposition_table;
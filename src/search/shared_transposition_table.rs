@@ -0,0 +1,373 @@
+//! A transposition table that can be shared between search threads without locks. Available with the `shared-tt` feature.
+//!
+//! Each entry is stored in three atomic words: two data words, and a check word holding the key's hash xor-ed with both data words.
+//! A reader that races with a writer may see a mix of two entries, but the check word will then almost never match, and the entry is treated as missing.
+//!
+//! Keys are only stored as 64-bit hashes, so two keys with the same hash are treated as the same position. Moves are stored through `CompressedMove`, and are decompressed in the position they are probed from, so a stored move is never illegal.
+
+use super::transposition_table::{Bound, ReplacementScheme, TTEntry, TranspositionStore};
+use crate::CompressedMove;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+// Layout of the second data word
+const DEPTH_MASK: u64 = 0xffff;
+const BOUND_SHIFT: u32 = 16;
+const HAS_MOVE_BIT: u64 = 1 << 18;
+const GENERATION_SHIFT: u32 = 24;
+const OCCUPIED_BIT: u64 = 1 << 32;
+
+#[derive(Debug, Default)]
+struct AtomicSlot {
+    check: AtomicU64,
+    move_and_score: AtomicU64,
+    info: AtomicU64,
+}
+
+/// A slot's contents, after validating the check word.
+#[derive(Clone, Copy)]
+struct SlotData {
+    hash: u64,
+    move_and_score: u64,
+    info: u64,
+}
+
+impl SlotData {
+    fn depth(self) -> u16 {
+        (self.info & DEPTH_MASK) as u16
+    }
+
+    fn generation(self) -> u8 {
+        (self.info >> GENERATION_SHIFT) as u8
+    }
+}
+
+impl AtomicSlot {
+    fn load(&self) -> Option<SlotData> {
+        let check = self.check.load(Ordering::Relaxed);
+        let move_and_score = self.move_and_score.load(Ordering::Relaxed);
+        let info = self.info.load(Ordering::Relaxed);
+        if info & OCCUPIED_BIT == 0 {
+            return None;
+        }
+        Some(SlotData {
+            hash: check ^ move_and_score ^ info,
+            move_and_score,
+            info,
+        })
+    }
+
+    fn store(&self, data: SlotData) {
+        self.move_and_score
+            .store(data.move_and_score, Ordering::Relaxed);
+        self.info.store(data.info, Ordering::Relaxed);
+        self.check.store(
+            data.hash ^ data.move_and_score ^ data.info,
+            Ordering::Relaxed,
+        );
+    }
+
+    fn clear(&self) {
+        self.check.store(0, Ordering::Relaxed);
+        self.move_and_score.store(0, Ordering::Relaxed);
+        self.info.store(0, Ordering::Relaxed);
+    }
+}
+
+/// A transposition table with a fixed memory budget, which can be probed and updated from several threads at once.
+///
+/// Like `TranspositionTable`, it is used through `TranspositionStore`. The trait is also implemented for `&SharedTranspositionTable`, so that every thread can probe and store through its own shared reference.
+/// The position passed to `probe` is needed to decompress stored moves.
+///
+/// # Examples
+/// ```rust
+/// use board_game_traits::search::shared_transposition_table::SharedTranspositionTable;
+/// use board_game_traits::search::transposition_table::{
+///     Bound, ReplacementScheme, TTEntry, TranspositionStore,
+/// };
+/// # use board_game_traits::Position;
+/// # use board_game_traits::testing::Nim;
+/// let table = SharedTranspositionTable::<u64, Nim>::new(1, ReplacementScheme::TwoTier);
+/// let position = Nim::start_position();
+/// let entry = TTEntry {
+///     best_move: Some(2),
+///     score: 0.5,
+///     depth: 4,
+///     bound: Bound::Lower,
+/// };
+/// std::thread::scope(|scope| {
+///     scope.spawn(|| {
+///         let mut table = &table;
+///         table.store(42, entry.clone());
+///     });
+/// });
+/// assert_eq!(table.probe(&position, &42), Some(entry));
+/// assert_eq!(table.probe(&position, &43), None);
+/// ```
+#[derive(Debug)]
+pub struct SharedTranspositionTable<K, P> {
+    slots: Vec<AtomicSlot>,
+    scheme: ReplacementScheme,
+    generation: AtomicU8,
+    phantom: PhantomData<fn(&K, P)>,
+}
+
+impl<K: Hash, P: CompressedMove> SharedTranspositionTable<K, P> {
+    /// Returns an empty table using approximately `size_mb` megabytes of memory. The table always has room for at least one bucket.
+    pub fn new(size_mb: usize, scheme: ReplacementScheme) -> Self {
+        let bucket_size = Self::bucket_size_for(scheme);
+        let num_buckets =
+            (size_mb * 1024 * 1024 / mem::size_of::<AtomicSlot>() / bucket_size).max(1);
+        SharedTranspositionTable {
+            slots: (0..num_buckets * bucket_size)
+                .map(|_| AtomicSlot::default())
+                .collect(),
+            scheme,
+            generation: AtomicU8::new(0),
+            phantom: PhantomData,
+        }
+    }
+
+    /// A stored move that is not legal in `position` is returned as None.
+    fn get(&self, position: &P, key: &K) -> Option<TTEntry<P::Move>> {
+        let hash = Self::hash(key);
+        let data = self.slots[self.bucket_range(hash)]
+            .iter()
+            .filter_map(AtomicSlot::load)
+            .find(|data| data.hash == hash)?;
+        let bound = match (data.info >> BOUND_SHIFT) & 0b11 {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        };
+        let best_move = if data.info & HAS_MOVE_BIT != 0 {
+            position.decompress((data.move_and_score >> 32) as u32)
+        } else {
+            None
+        };
+        Some(TTEntry {
+            best_move,
+            score: f32::from_bits(data.move_and_score as u32),
+            depth: data.depth(),
+            bound,
+        })
+    }
+
+    /// An existing entry for the same key is always overwritten, unless it is in a depth-preferred slot and has a greater depth.
+    fn insert(&self, key: &K, entry: TTEntry<P::Move>) {
+        let generation = self.generation.load(Ordering::Relaxed);
+        let hash = Self::hash(key);
+        let bucket = &self.slots[self.bucket_range(hash)];
+        let slot_index = match self.scheme {
+            ReplacementScheme::AlwaysReplace => 0,
+            ReplacementScheme::DepthPreferred => {
+                if Self::may_replace(bucket[0].load(), &entry, generation) {
+                    0
+                } else {
+                    return;
+                }
+            }
+            ReplacementScheme::TwoTier => {
+                if Self::may_replace(bucket[0].load(), &entry, generation) {
+                    // The always-replace slot may hold an older entry for this key
+                    if matches!(bucket[1].load(), Some(data) if data.hash == hash) {
+                        bucket[1].clear();
+                    }
+                    0
+                } else {
+                    1
+                }
+            }
+        };
+        let compressed_move = entry.best_move.as_ref().map_or(0, P::compress);
+        let bound = match entry.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        let mut info = entry.depth as u64
+            | bound << BOUND_SHIFT
+            | (generation as u64) << GENERATION_SHIFT
+            | OCCUPIED_BIT;
+        if entry.best_move.is_some() {
+            info |= HAS_MOVE_BIT;
+        }
+        bucket[slot_index].store(SlotData {
+            hash,
+            move_and_score: (compressed_move as u64) << 32 | entry.score.to_bits() as u64,
+            info,
+        });
+    }
+
+    fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.clear();
+        }
+    }
+
+    /// Based on a sample of the first 1000 slots.
+    fn fill_permille(&self) -> usize {
+        let generation = self.generation.load(Ordering::Relaxed);
+        let sample_size = self.slots.len().min(1000);
+        let used = self.slots[..sample_size]
+            .iter()
+            .filter_map(AtomicSlot::load)
+            .filter(|data| data.generation() == generation)
+            .count();
+        used * 1000 / sample_size
+    }
+
+    fn hash(key: &K) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn bucket_size_for(scheme: ReplacementScheme) -> usize {
+        match scheme {
+            ReplacementScheme::AlwaysReplace | ReplacementScheme::DepthPreferred => 1,
+            ReplacementScheme::TwoTier => 2,
+        }
+    }
+
+    fn bucket_range(&self, hash: u64) -> std::ops::Range<usize> {
+        let bucket_size = Self::bucket_size_for(self.scheme);
+        let bucket = (hash % (self.slots.len() / bucket_size) as u64) as usize;
+        bucket * bucket_size..(bucket + 1) * bucket_size
+    }
+
+    fn may_replace(old: Option<SlotData>, new: &TTEntry<P::Move>, generation: u8) -> bool {
+        match old {
+            None => true,
+            Some(old) => old.generation() != generation || new.depth >= old.depth(),
+        }
+    }
+}
+
+impl<K: Hash, P: CompressedMove> TranspositionStore<P> for SharedTranspositionTable<K, P> {
+    type Key = K;
+
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn probe(&self, position: &P, key: &K) -> Option<TTEntry<P::Move>> {
+        self.get(position, key)
+    }
+
+    fn store(&mut self, key: K, entry: TTEntry<P::Move>) {
+        self.insert(&key, entry)
+    }
+
+    fn new_search(&mut self) {
+        SharedTranspositionTable::new_search(self)
+    }
+
+    fn clear(&mut self) {
+        SharedTranspositionTable::clear(self)
+    }
+
+    fn fill_permille(&self) -> usize {
+        SharedTranspositionTable::fill_permille(self)
+    }
+}
+
+impl<K: Hash, P: CompressedMove> TranspositionStore<P> for &SharedTranspositionTable<K, P> {
+    type Key = K;
+
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn probe(&self, position: &P, key: &K) -> Option<TTEntry<P::Move>> {
+        self.get(position, key)
+    }
+
+    fn store(&mut self, key: K, entry: TTEntry<P::Move>) {
+        self.insert(&key, entry)
+    }
+
+    fn new_search(&mut self) {
+        SharedTranspositionTable::new_search(self)
+    }
+
+    fn clear(&mut self) {
+        SharedTranspositionTable::clear(self)
+    }
+
+    fn fill_permille(&self) -> usize {
+        SharedTranspositionTable::fill_permille(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::transposition_table::TranspositionTable;
+    use crate::testing::Nim;
+    use crate::{Color, Position};
+
+    fn entry(best_move: u32, depth: u16) -> TTEntry<u32> {
+        TTEntry {
+            best_move: Some(best_move),
+            score: 0.5,
+            depth,
+            bound: Bound::Exact,
+        }
+    }
+
+    /// Runs the same operations on any table with a single depth-preferred slot.
+    fn check_depth_preferred<T: TranspositionStore<Nim, Key = u64>>(mut table: T) {
+        let position = Nim::start_position();
+        assert_eq!(table.capacity(), 1);
+        table.store(1, entry(3, 5));
+        assert_eq!(table.probe(&position, &1), Some(entry(3, 5)));
+        assert_eq!(table.probe(&position, &2), None);
+
+        // A shallower entry does not replace a deeper one from the same search
+        table.store(2, entry(2, 4));
+        assert_eq!(table.probe(&position, &2), None);
+        assert_eq!(table.fill_permille(), 1000);
+
+        table.new_search();
+        assert_eq!(table.fill_permille(), 0);
+        table.store(2, entry(2, 4));
+        assert_eq!(table.probe(&position, &1), None);
+        assert_eq!(table.probe(&position, &2), Some(entry(2, 4)));
+
+        table.clear();
+        assert_eq!(table.probe(&position, &2), None);
+    }
+
+    #[test]
+    fn both_tables_behave_the_same() {
+        // Request a tiny table, which is rounded up to a single bucket
+        check_depth_preferred(TranspositionTable::<u64, Nim>::new(
+            0,
+            ReplacementScheme::DepthPreferred,
+        ));
+        check_depth_preferred(SharedTranspositionTable::<u64, Nim>::new(
+            0,
+            ReplacementScheme::DepthPreferred,
+        ));
+        let shared =
+            SharedTranspositionTable::<u64, Nim>::new(0, ReplacementScheme::DepthPreferred);
+        check_depth_preferred(&shared);
+    }
+
+    #[test]
+    fn illegal_stored_moves_are_dropped() {
+        let mut table = SharedTranspositionTable::<u64, Nim>::new(1, ReplacementScheme::TwoTier);
+        table.store(7, entry(3, 1));
+        let probed = table.probe(&Nim(2, Color::White), &7).unwrap();
+        assert_eq!(probed.best_move, None);
+        assert_eq!(probed.depth, 1);
+    }
+}
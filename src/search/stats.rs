@@ -0,0 +1,118 @@
+//! Counters for instrumenting a search.
+//!
+//! Search algorithms update the counters as they go. Besides the standard counters, any number of named user-defined counters can be added, for measuring engine-specific features without changing this struct.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Statistics collected during a search.
+/// # Examples
+/// ```rust
+/// use board_game_traits::search::stats::SearchStats;
+/// let mut stats = SearchStats::default();
+/// stats.nodes += 100;
+/// stats.tt_probes += 80;
+/// stats.tt_hits += 20;
+/// stats.increment("singular extensions");
+/// stats.update_max_depth(7);
+/// assert_eq!(stats.tt_hit_rate(), 0.25);
+/// assert_eq!(stats.counter("singular extensions"), 1);
+/// assert_eq!(stats.counter("razoring"), 0);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Nodes visited by the main search, excluding quiescence search.
+    pub nodes: u64,
+    /// Nodes visited by the quiescence search.
+    pub qnodes: u64,
+    /// Lookups in the transposition table.
+    pub tt_probes: u64,
+    /// Lookups in the transposition table that found an entry.
+    pub tt_hits: u64,
+    pub beta_cutoffs: u64,
+    pub null_move_cutoffs: u64,
+    /// The deepest ply reached, including extensions and quiescence search.
+    pub max_depth: u16,
+    counters: BTreeMap<&'static str, u64>,
+}
+
+impl SearchStats {
+    /// Returns the total number of nodes, including quiescence nodes.
+    pub fn total_nodes(&self) -> u64 {
+        self.nodes + self.qnodes
+    }
+
+    /// Returns the fraction of transposition table probes that found an entry, or 0 if there were no probes.
+    pub fn tt_hit_rate(&self) -> f64 {
+        if self.tt_probes == 0 {
+            0.0
+        } else {
+            self.tt_hits as f64 / self.tt_probes as f64
+        }
+    }
+
+    /// Raises `max_depth` to `depth`, if it is deeper.
+    pub fn update_max_depth(&mut self, depth: u16) {
+        self.max_depth = self.max_depth.max(depth);
+    }
+
+    /// Adds one to a user-defined counter, creating it if needed.
+    pub fn increment(&mut self, counter: &'static str) {
+        self.add(counter, 1)
+    }
+
+    /// Adds `amount` to a user-defined counter, creating it if needed.
+    pub fn add(&mut self, counter: &'static str, amount: u64) {
+        *self.counters.entry(counter).or_insert(0) += amount;
+    }
+
+    /// Returns the value of a user-defined counter, or 0 if it was never updated.
+    pub fn counter(&self, counter: &str) -> u64 {
+        self.counters.get(counter).copied().unwrap_or(0)
+    }
+
+    /// Returns every user-defined counter, ordered by name.
+    pub fn counters(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        self.counters.iter().map(|(&name, &value)| (name, value))
+    }
+
+    /// Adds the statistics from another search, for example from another search thread. `max_depth` becomes the deeper of the two.
+    pub fn merge(&mut self, other: &SearchStats) {
+        self.nodes += other.nodes;
+        self.qnodes += other.qnodes;
+        self.tt_probes += other.tt_probes;
+        self.tt_hits += other.tt_hits;
+        self.beta_cutoffs += other.beta_cutoffs;
+        self.null_move_cutoffs += other.null_move_cutoffs;
+        self.update_max_depth(other.max_depth);
+        for (name, value) in other.counters() {
+            self.add(name, value);
+        }
+    }
+
+    /// Resets every counter to zero, and removes the user-defined counters.
+    pub fn clear(&mut self) {
+        *self = SearchStats::default();
+    }
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            fmt,
+            "nodes {} qnodes {} tt hits {}/{} ({:.1}%) beta cutoffs {} null move cutoffs {} max depth {}",
+            self.nodes,
+            self.qnodes,
+            self.tt_hits,
+            self.tt_probes,
+            self.tt_hit_rate() * 100.0,
+            self.beta_cutoffs,
+            self.null_move_cutoffs,
+            self.max_depth
+        )?;
+        for (name, value) in self.counters() {
+            write!(fmt, " {} {}", name, value)?;
+        }
+        Ok(())
+    }
+}
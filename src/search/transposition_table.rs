@@ -0,0 +1,260 @@
+//! A fixed-size hash table for caching search results across transpositions.
+//!
+//! Keys can be any hashable type, such as `HashablePosition::HashPosition`, or an incrementally updated Zobrist hash.
+//! For games that implement `SymmetricPosition`, `probe_canonical` and `store_canonical` share entries between all symmetric positions.
+//! Both this table and the thread-safe `SharedTranspositionTable` are used through the `TranspositionStore` trait, so search code can be generic over them.
+
+use crate::{Position, SymmetricPosition};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem;
+
+/// How a stored score relates to the true value of the position.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Bound {
+    /// The score is exact.
+    Exact,
+    /// The search failed high, so the true value is at least the score.
+    Lower,
+    /// The search failed low, so the true value is at most the score.
+    Upper,
+}
+
+/// Decides which entry is overwritten when a new entry is stored in an occupied slot.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ReplacementScheme {
+    /// New entries always overwrite the old entry.
+    AlwaysReplace,
+    /// New entries do not overwrite entries from the current search with a greater depth.
+    DepthPreferred,
+    /// Each bucket has a depth-preferred slot and an always-replace slot. Entries that do not go into the first slot go into the second.
+    TwoTier,
+}
+
+/// The result of a previous search of a position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TTEntry<M> {
+    /// The best move found, if any. For fail-low nodes, there is usually no best move.
+    pub best_move: Option<M>,
+    /// The score of the position, from white's perspective.
+    pub score: f32,
+    /// The remaining depth that the position was searched to.
+    pub depth: u16,
+    pub bound: Bound,
+}
+
+/// The operations of `TranspositionTable` and `SharedTranspositionTable`, so that search code can be generic over them.
+///
+/// `SharedTranspositionTable` also implements it through a shared reference, so that each search thread can hold its own `&SharedTranspositionTable`.
+/// # Examples
+/// ```rust
+/// use board_game_traits::search::transposition_table::{
+///     Bound, ReplacementScheme, TTEntry, TranspositionStore, TranspositionTable,
+/// };
+/// use board_game_traits::Position;
+/// # use board_game_traits::testing::Nim;
+/// fn remember_best_move<T: TranspositionStore<Nim, Key = u32>>(table: &mut T, position: &Nim, mv: u32) {
+///     let entry = TTEntry { best_move: Some(mv), score: 0.0, depth: 1, bound: Bound::Exact };
+///     table.store(position.0, entry);
+/// }
+///
+/// let mut table = TranspositionTable::<u32, Nim>::new(1, ReplacementScheme::AlwaysReplace);
+/// let position = Nim::start_position();
+/// remember_best_move(&mut table, &position, 2);
+/// assert_eq!(table.probe(&position, &10).unwrap().best_move, Some(2));
+/// assert_eq!(table.probe(&position, &9), None);
+/// ```
+pub trait TranspositionStore<P: Position> {
+    type Key;
+
+    /// Returns the total number of entries the table can hold.
+    fn capacity(&self) -> usize;
+
+    /// Returns a copy of the stored entry for the key, if any. `position` must be the position the key was computed from.
+    fn probe(&self, position: &P, key: &Self::Key) -> Option<TTEntry<P::Move>>;
+
+    /// Stores an entry for the key, subject to the replacement scheme.
+    fn store(&mut self, key: Self::Key, entry: TTEntry<P::Move>);
+
+    /// Marks the start of a new search. Entries from earlier searches are replaced before entries from the current search.
+    fn new_search(&mut self);
+
+    /// Removes all entries.
+    fn clear(&mut self);
+
+    /// Returns how full the table is with entries from the current search, in permille.
+    fn fill_permille(&self) -> usize;
+
+    /// Returns a copy of the stored entry for the position's canonical form, if any, with the best move mapped back to this position.
+    fn probe_canonical(&self, position: &P) -> Option<TTEntry<P::Move>>
+    where
+        P: SymmetricPosition<HashPosition = Self::Key>,
+    {
+        let (canonical, symmetry) = position.canonicalize();
+        let entry = self.probe(&canonical, &canonical.hash_position())?;
+        Some(TTEntry {
+            best_move: entry
+                .best_move
+                .as_ref()
+                .map(|mv| position.map_move_from_canonical(mv, symmetry)),
+            ..entry
+        })
+    }
+
+    /// Stores an entry for the position's canonical form, with the best move mapped to the canonical form, subject to the replacement scheme.
+    fn store_canonical(&mut self, position: &P, entry: TTEntry<P::Move>)
+    where
+        P: SymmetricPosition<HashPosition = Self::Key>,
+    {
+        let (canonical, symmetry) = position.canonicalize();
+        let entry = TTEntry {
+            best_move: entry
+                .best_move
+                .map(|mv| position.map_move_to_canonical(&mv, symmetry)),
+            ..entry
+        };
+        self.store(canonical.hash_position(), entry);
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Slot<K, M> {
+    key: K,
+    entry: TTEntry<M>,
+    generation: u8,
+}
+
+/// A transposition table with a fixed memory budget, for positions of type `P` with keys of type `K`. Used through `TranspositionStore`.
+#[derive(Clone, Debug)]
+pub struct TranspositionTable<K, P: Position> {
+    slots: Vec<Option<Slot<K, P::Move>>>,
+    scheme: ReplacementScheme,
+    generation: u8,
+    phantom: PhantomData<fn(&K, P)>,
+}
+
+impl<K: Hash + Eq, P: Position> TranspositionTable<K, P> {
+    /// Returns an empty table using approximately `size_mb` megabytes of memory. The table always has room for at least one bucket.
+    pub fn new(size_mb: usize, scheme: ReplacementScheme) -> Self {
+        let bucket_size = Self::bucket_size_for(scheme);
+        let slot_size = mem::size_of::<Option<Slot<K, P::Move>>>().max(1);
+        let num_buckets = (size_mb * 1024 * 1024 / slot_size / bucket_size).max(1);
+        TranspositionTable {
+            slots: (0..num_buckets * bucket_size).map(|_| None).collect(),
+            scheme,
+            generation: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    fn get(&self, key: &K) -> Option<&TTEntry<P::Move>> {
+        self.bucket(key)
+            .iter()
+            .flatten()
+            .find(|slot| slot.key == *key)
+            .map(|slot| &slot.entry)
+    }
+
+    /// An existing entry for the same key is always overwritten, unless it is in a depth-preferred slot and has a greater depth.
+    fn insert(&mut self, key: K, entry: TTEntry<P::Move>) {
+        let generation = self.generation;
+        let scheme = self.scheme;
+        let bucket = self.bucket_mut(&key);
+        let slot_index = match scheme {
+            ReplacementScheme::AlwaysReplace => 0,
+            ReplacementScheme::DepthPreferred => {
+                if Self::may_replace(&bucket[0], &entry, generation) {
+                    0
+                } else {
+                    return;
+                }
+            }
+            ReplacementScheme::TwoTier => {
+                if Self::may_replace(&bucket[0], &entry, generation) {
+                    // The always-replace slot may hold an older entry for this key
+                    if matches!(&bucket[1], Some(slot) if slot.key == key) {
+                        bucket[1] = None;
+                    }
+                    0
+                } else {
+                    1
+                }
+            }
+        };
+        bucket[slot_index] = Some(Slot {
+            key,
+            entry,
+            generation,
+        });
+    }
+
+    fn bucket_size_for(scheme: ReplacementScheme) -> usize {
+        match scheme {
+            ReplacementScheme::AlwaysReplace | ReplacementScheme::DepthPreferred => 1,
+            ReplacementScheme::TwoTier => 2,
+        }
+    }
+
+    fn bucket_range(&self, key: &K) -> std::ops::Range<usize> {
+        let bucket_size = Self::bucket_size_for(self.scheme);
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let bucket = (hasher.finish() % (self.slots.len() / bucket_size) as u64) as usize;
+        bucket * bucket_size..(bucket + 1) * bucket_size
+    }
+
+    fn bucket(&self, key: &K) -> &[Option<Slot<K, P::Move>>] {
+        let range = self.bucket_range(key);
+        &self.slots[range]
+    }
+
+    fn bucket_mut(&mut self, key: &K) -> &mut [Option<Slot<K, P::Move>>] {
+        let range = self.bucket_range(key);
+        &mut self.slots[range]
+    }
+
+    fn may_replace(old: &Option<Slot<K, P::Move>>, new: &TTEntry<P::Move>, generation: u8) -> bool {
+        match old {
+            None => true,
+            Some(old) => old.generation != generation || new.depth >= old.entry.depth,
+        }
+    }
+}
+
+impl<K: Hash + Eq, P: Position> TranspositionStore<P> for TranspositionTable<K, P> {
+    type Key = K;
+
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn probe(&self, _position: &P, key: &K) -> Option<TTEntry<P::Move>> {
+        self.get(key).cloned()
+    }
+
+    fn store(&mut self, key: K, entry: TTEntry<P::Move>) {
+        self.insert(key, entry)
+    }
+
+    fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    fn clear(&mut self) {
+        for slot in self.slots.iter_mut() {
+            *slot = None;
+        }
+    }
+
+    /// Based on a sample of the first 1000 slots.
+    fn fill_permille(&self) -> usize {
+        let sample_size = self.slots.len().min(1000);
+        let used = self.slots[..sample_size]
+            .iter()
+            .flatten()
+            .filter(|slot| slot.generation == self.generation)
+            .count();
+        used * 1000 / sample_size
+    }
+}
@@ -0,0 +1,174 @@
+//! Describing and parsing game variant options, such as board sizes or komi, without game-specific code.
+//!
+//! A game's `Position::Settings` type can implement `DescribeSettings`, so that GUIs can present its options and servers can read them from key-value strings, for example from a configuration file or a protocol command.
+
+use std::error;
+use std::fmt;
+
+/// The type of value a setting takes, with its allowed range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SettingKind {
+    Bool,
+    /// An integer within an inclusive range.
+    Integer {
+        min: i64,
+        max: i64,
+    },
+    /// One of a fixed set of values.
+    Choice(Vec<String>),
+    /// Any string.
+    String,
+}
+
+/// A single named setting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SettingDescription {
+    pub name: String,
+    pub kind: SettingKind,
+    /// The default value, in the same form as it is parsed.
+    pub default: String,
+}
+
+impl SettingDescription {
+    pub fn new(name: &str, kind: SettingKind, default: &str) -> Self {
+        SettingDescription {
+            name: name.to_string(),
+            kind,
+            default: default.to_string(),
+        }
+    }
+
+    /// Checks that the value is allowed by the setting's kind.
+    pub fn validate(&self, value: &str) -> Result<(), SettingError> {
+        let is_valid = match &self.kind {
+            SettingKind::Bool => value == "true" || value == "false",
+            SettingKind::Integer { min, max } => value
+                .parse::<i64>()
+                .is_ok_and(|value| (*min..=*max).contains(&value)),
+            SettingKind::Choice(choices) => choices.iter().any(|choice| choice == value),
+            SettingKind::String => true,
+        };
+        if is_valid {
+            Ok(())
+        } else {
+            Err(SettingError::InvalidValue {
+                name: self.name.clone(),
+                value: value.to_string(),
+            })
+        }
+    }
+}
+
+/// The error returned when a setting cannot be set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SettingError {
+    /// The settings have no option with this name.
+    UnknownSetting(String),
+    /// The value is not allowed for the option.
+    InvalidValue { name: String, value: String },
+}
+
+impl fmt::Display for SettingError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            SettingError::UnknownSetting(name) => write!(fmt, "unknown setting \"{}\"", name),
+            SettingError::InvalidValue { name, value } => {
+                write!(fmt, "invalid value \"{}\" for setting \"{}\"", value, name)
+            }
+        }
+    }
+}
+
+impl error::Error for SettingError {}
+
+/// Settings that can describe their options, and be configured from key-value strings.
+///
+/// Implemented for `()`, which has no options.
+/// # Examples
+/// ```rust
+/// use board_game_traits::settings::{DescribeSettings, SettingDescription, SettingError, SettingKind};
+///
+/// #[derive(Debug, Default, PartialEq)]
+/// struct GoSettings {
+///     size: u8,
+///     komi_halves: i64,
+/// }
+///
+/// impl DescribeSettings for GoSettings {
+///     fn describe() -> Vec<SettingDescription> {
+///         vec![
+///             SettingDescription::new("size", SettingKind::Choice(vec!["9".into(), "13".into(), "19".into()]), "19"),
+///             SettingDescription::new("komi_halves", SettingKind::Integer { min: -100, max: 100 }, "13"),
+///         ]
+///     }
+///
+///     fn set_value(&mut self, name: &str, value: &str) -> Result<(), SettingError> {
+///         self.validate(name, value)?;
+///         match name {
+///             "size" => self.size = value.parse().unwrap(),
+///             _ => self.komi_halves = value.parse().unwrap(),
+///         }
+///         Ok(())
+///     }
+///
+///     fn values(&self) -> Vec<(String, String)> {
+///         vec![
+///             ("size".to_string(), self.size.to_string()),
+///             ("komi_halves".to_string(), self.komi_halves.to_string()),
+///         ]
+///     }
+/// }
+///
+/// let settings = GoSettings::from_values(&[("size", "9")]).unwrap();
+/// assert_eq!(settings, GoSettings { size: 9, komi_halves: 13 });
+/// assert!(GoSettings::from_values(&[("size", "10")]).is_err());
+/// assert!(GoSettings::from_values(&[("handicap", "2")]).is_err());
+/// ```
+pub trait DescribeSettings: Sized {
+    /// Returns every option, in the order they should be presented.
+    fn describe() -> Vec<SettingDescription>;
+
+    /// Sets an option from its string form.
+    fn set_value(&mut self, name: &str, value: &str) -> Result<(), SettingError>;
+
+    /// Returns the current value of every option, in the same form as they are parsed.
+    fn values(&self) -> Vec<(String, String)>;
+
+    /// Checks that the option exists, and that the value is allowed by its description.
+    fn validate(&self, name: &str, value: &str) -> Result<(), SettingError> {
+        Self::describe()
+            .iter()
+            .find(|description| description.name == name)
+            .ok_or_else(|| SettingError::UnknownSetting(name.to_string()))?
+            .validate(value)
+    }
+
+    /// Creates settings with every option at its described default, then sets the given options in order.
+    fn from_values<K: AsRef<str>, V: AsRef<str>>(values: &[(K, V)]) -> Result<Self, SettingError>
+    where
+        Self: Default,
+    {
+        let mut settings = Self::default();
+        for description in Self::describe() {
+            settings.set_value(&description.name, &description.default)?;
+        }
+        for (name, value) in values {
+            settings.set_value(name.as_ref(), value.as_ref())?;
+        }
+        Ok(settings)
+    }
+}
+
+impl DescribeSettings for () {
+    fn describe() -> Vec<SettingDescription> {
+        vec![]
+    }
+
+    fn set_value(&mut self, name: &str, _value: &str) -> Result<(), SettingError> {
+        Err(SettingError::UnknownSetting(name.to_string()))
+    }
+
+    fn values(&self) -> Vec<(String, String)> {
+        vec![]
+    }
+}
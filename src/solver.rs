@@ -0,0 +1,744 @@
+//! Proof-number search, for strongly solving small games such as tic-tac-toe, or connect four and Hex on small boards.
+//!
+//! Proof-number search proves or disproves a single goal for one player, the attacker. It repeatedly expands the most-proving node: the leaf where the least work is needed to prove or disprove the goal.
+//! To find the game-theoretic value, the solver first tries to prove that the side to move wins, and if that fails, that it does not lose.
+//!
+//! The search tree is held in memory, and each search is limited to a maximum number of nodes.
+//! `solve_dfpn` uses depth-first proof-number search instead, which only keeps the current path and a transposition table in memory, and re-searches subtrees as needed.
+//! If the game has a `MAX_GAME_LENGTH`, positions that many plies below the position being solved are treated as draws.
+
+use crate::{Color, GameResult, HashablePosition, Position, SymmetricPosition};
+use std::collections::HashMap;
+
+/// A proof of a game-theoretic value, as a tree of moves.
+///
+/// At nodes where the winning side (or the side holding the draw) is to move, the tree contains a single move that keeps the value. At the other nodes, it contains every legal move.
+/// Leaves are positions where the game is decided. If the solver used a transposition table, leaves may also be positions that were solved elsewhere in the tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofTree<M> {
+    pub children: Vec<(M, ProofTree<M>)>,
+}
+
+impl<M> ProofTree<M> {
+    /// Returns the number of nodes in the tree, including the root.
+    pub fn size(&self) -> usize {
+        1 + self
+            .children
+            .iter()
+            .map(|(_, child)| child.size())
+            .sum::<usize>()
+    }
+}
+
+/// The outcome of solving a position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolveResult<M> {
+    /// The game-theoretic value of the position, or None if the node limit was reached first.
+    pub result: Option<GameResult>,
+    /// A proof of the value, if the position was solved.
+    pub proof_tree: Option<ProofTree<M>>,
+    /// The total number of nodes created, over all searches.
+    pub nodes: usize,
+}
+
+/// Solves the position with proof-number search. Each search gives up once it has created `max_nodes` nodes.
+///
+/// The position is restored to its original state before returning.
+pub fn solve<P: Position>(position: &mut P, max_nodes: usize) -> SolveResult<P::Move> {
+    solve_with_cache(position, max_nodes, &mut NoCache)
+}
+
+/// Solves the position with proof-number search, and uses a transposition table to avoid solving the same position twice. Each search gives up once it has created `max_nodes` nodes.
+///
+/// Solved positions are shared through their `HashPosition`, so it must include everything that affects the game's outcome. In particular, rules such as draws by repetition depend on the path to the position, and may give incorrect results. The same goes for `MAX_GAME_LENGTH`, unless the position includes the ply count.
+///
+/// The position is restored to its original state before returning.
+pub fn solve_with_transpositions<P: HashablePosition>(
+    position: &mut P,
+    max_nodes: usize,
+) -> SolveResult<P::Move> {
+    solve_with_cache(position, max_nodes, &mut HashCache(HashMap::new()))
+}
+
+/// Solves the position with proof-number search, and uses a transposition table shared between all symmetric positions. Each search gives up once it has created `max_nodes` nodes.
+///
+/// The same caveats as for `solve_with_transpositions` apply, for the canonical hash.
+///
+/// The position is restored to its original state before returning.
+pub fn solve_with_symmetries<P: SymmetricPosition>(
+    position: &mut P,
+    max_nodes: usize,
+) -> SolveResult<P::Move> {
+    solve_with_cache(position, max_nodes, &mut SymmetricCache(HashMap::new()))
+}
+
+/// Solves the position with depth-first proof-number search (DFPN). Each search gives up once it has visited `max_nodes` nodes.
+///
+/// Instead of the whole tree, only the proof and disproof numbers of visited positions are kept, in a transposition table keyed by `HashPosition`. The same caveats as for `solve_with_transpositions` apply, except that the ply count is included in the key if the game has a `MAX_GAME_LENGTH`.
+/// The proof tree is rebuilt from the transposition table.
+///
+/// The position is restored to its original state before returning.
+pub fn solve_dfpn<P: HashablePosition>(position: &mut P, max_nodes: usize) -> SolveResult<P::Move> {
+    let attacker = position.side_to_move();
+    let mut total_nodes = 0;
+    for &goal in [Goal::Win, Goal::NotLose].iter() {
+        let mut search = Dfpn {
+            table: HashMap::new(),
+            attacker,
+            goal,
+            nodes: 0,
+            max_nodes,
+        };
+        let (proof, disproof) = search.mid(position, 0, INFINITY, INFINITY);
+        total_nodes += search.nodes;
+        let proven = if proof == 0 {
+            Some(true)
+        } else if disproof == 0 {
+            Some(false)
+        } else {
+            None
+        };
+        let result = match (goal, proven) {
+            (_, None) => None,
+            (Goal::Win, Some(false)) => continue,
+            (Goal::Win, Some(true)) => Some(GameResult::win_by(attacker)),
+            (Goal::NotLose, Some(true)) => Some(GameResult::Draw),
+            (Goal::NotLose, Some(false)) => Some(GameResult::win_by(!attacker)),
+        };
+        return SolveResult {
+            result,
+            proof_tree: proven.map(|proven| search.proof_tree(position, 0, proven)),
+            nodes: total_nodes,
+        };
+    }
+    unreachable!()
+}
+
+/// Caches positions that have been proven or disproven in the current search.
+trait SolvedCache<P> {
+    fn get(&self, position: &P) -> Option<bool>;
+    fn insert(&mut self, position: &P, proven: bool);
+    fn clear(&mut self);
+}
+
+struct NoCache;
+
+impl<P> SolvedCache<P> for NoCache {
+    fn get(&self, _position: &P) -> Option<bool> {
+        None
+    }
+
+    fn insert(&mut self, _position: &P, _proven: bool) {}
+
+    fn clear(&mut self) {}
+}
+
+struct HashCache<P: HashablePosition>(HashMap<P::HashPosition, bool>);
+
+impl<P: HashablePosition> SolvedCache<P> for HashCache<P> {
+    fn get(&self, position: &P) -> Option<bool> {
+        self.0.get(&position.hash_position()).copied()
+    }
+
+    fn insert(&mut self, position: &P, proven: bool) {
+        self.0.insert(position.hash_position(), proven);
+    }
+
+    fn clear(&mut self) {
+        self.0.clear()
+    }
+}
+
+struct SymmetricCache<P: SymmetricPosition>(HashMap<P::HashPosition, bool>);
+
+impl<P: SymmetricPosition> SolvedCache<P> for SymmetricCache<P> {
+    fn get(&self, position: &P) -> Option<bool> {
+        self.0.get(&position.canonical_hash()).copied()
+    }
+
+    fn insert(&mut self, position: &P, proven: bool) {
+        self.0.insert(position.canonical_hash(), proven);
+    }
+
+    fn clear(&mut self) {
+        self.0.clear()
+    }
+}
+
+const INFINITY: u64 = u64::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Goal {
+    Win,
+    NotLose,
+}
+
+impl Goal {
+    fn is_proven_by(self, result: GameResult, attacker: Color) -> bool {
+        match self {
+            Goal::Win => result == GameResult::win_by(attacker),
+            Goal::NotLose => result != GameResult::win_by(!attacker),
+        }
+    }
+}
+
+struct Node<M> {
+    mv: Option<M>,
+    children: Vec<usize>,
+    proof: u64,
+    disproof: u64,
+    expanded: bool,
+}
+
+impl<M> Node<M> {
+    fn new(mv: Option<M>) -> Self {
+        Node {
+            mv,
+            children: vec![],
+            proof: 1,
+            disproof: 1,
+            expanded: false,
+        }
+    }
+
+    fn set_solved(&mut self, proven: bool) {
+        if proven {
+            self.proof = 0;
+            self.disproof = INFINITY;
+        } else {
+            self.proof = INFINITY;
+            self.disproof = 0;
+        }
+    }
+
+    fn is_solved(&self) -> bool {
+        self.proof == 0 || self.disproof == 0
+    }
+}
+
+fn solve_with_cache<P, C>(position: &mut P, max_nodes: usize, cache: &mut C) -> SolveResult<P::Move>
+where
+    P: Position,
+    C: SolvedCache<P>,
+{
+    let attacker = position.side_to_move();
+    let mut total_nodes = 0;
+    for &goal in [Goal::Win, Goal::NotLose].iter() {
+        cache.clear();
+        let mut search = ProofNumberSearch {
+            nodes: vec![Node::new(None)],
+            attacker,
+            goal,
+        };
+        let proven = search.run(position, max_nodes, cache);
+        total_nodes += search.nodes.len();
+        let result = match (goal, proven) {
+            (_, None) => None,
+            (Goal::Win, Some(false)) => continue,
+            (Goal::Win, Some(true)) => Some(GameResult::win_by(attacker)),
+            (Goal::NotLose, Some(true)) => Some(GameResult::Draw),
+            (Goal::NotLose, Some(false)) => Some(GameResult::win_by(!attacker)),
+        };
+        return SolveResult {
+            result,
+            proof_tree: result.map(|_| search.proof_tree(0, position)),
+            nodes: total_nodes,
+        };
+    }
+    unreachable!()
+}
+
+struct ProofNumberSearch<M> {
+    nodes: Vec<Node<M>>,
+    attacker: Color,
+    goal: Goal,
+}
+
+impl<M: Clone> ProofNumberSearch<M> {
+    /// Returns whether the goal was proven, or None if the node limit was reached.
+    fn run<P, C>(&mut self, position: &mut P, max_nodes: usize, cache: &mut C) -> Option<bool>
+    where
+        P: Position<Move = M>,
+        C: SolvedCache<P>,
+    {
+        if let Some(result) = position.game_result() {
+            self.nodes[0].set_solved(self.goal.is_proven_by(result, self.attacker));
+        }
+        while !self.nodes[0].is_solved() {
+            if self.nodes.len() >= max_nodes {
+                return None;
+            }
+
+            // Walk down to the most-proving node
+            let mut path = vec![0];
+            let mut reverse_moves = vec![];
+            let mut node = 0;
+            while self.nodes[node].expanded {
+                let attacker_to_move = position.side_to_move() == self.attacker;
+                node = *self.nodes[node]
+                    .children
+                    .iter()
+                    .min_by_key(|&&child| {
+                        if attacker_to_move {
+                            self.nodes[child].proof
+                        } else {
+                            self.nodes[child].disproof
+                        }
+                    })
+                    .unwrap();
+                let mv = self.nodes[node].mv.clone().unwrap();
+                reverse_moves.push(position.do_move(mv));
+                path.push(node);
+            }
+
+            self.expand(node, path.len() as u32 - 1, position, cache);
+
+            // Update the proof and disproof numbers back up to the root
+            for &node in path.iter().rev() {
+                self.update(node, position);
+                if self.nodes[node].is_solved() {
+                    cache.insert(position, self.nodes[node].proof == 0);
+                }
+                if let Some(reverse_move) = reverse_moves.pop() {
+                    position.reverse_move(reverse_move);
+                }
+            }
+        }
+        Some(self.nodes[0].proof == 0)
+    }
+
+    /// Expands a leaf `depth` plies below the root.
+    fn expand<P, C>(&mut self, node: usize, depth: u32, position: &mut P, cache: &C)
+    where
+        P: Position<Move = M>,
+        C: SolvedCache<P>,
+    {
+        let mut moves = vec![];
+        position.generate_moves(&mut moves);
+        self.nodes[node].expanded = true;
+        if moves.is_empty() {
+            // The game is not decided, but there are no legal moves. Treat it as a draw.
+            let proven = self.goal.is_proven_by(GameResult::Draw, self.attacker);
+            self.nodes[node].set_solved(proven);
+            return;
+        }
+        for mv in moves {
+            let mut child = Node::new(Some(mv.clone()));
+            let reverse_move = position.do_move(mv);
+            if let Some(proven) = cache.get(position) {
+                child.set_solved(proven);
+            } else if let Some(result) = position.game_result() {
+                child.set_solved(self.goal.is_proven_by(result, self.attacker));
+            } else if P::MAX_GAME_LENGTH.is_some_and(|length| depth + 1 >= length) {
+                child.set_solved(self.goal.is_proven_by(GameResult::Draw, self.attacker));
+            }
+            position.reverse_move(reverse_move);
+            let child_index = self.nodes.len();
+            self.nodes[node].children.push(child_index);
+            self.nodes.push(child);
+        }
+    }
+
+    fn update<P: Position<Move = M>>(&mut self, node: usize, position: &P) {
+        if self.nodes[node].children.is_empty() {
+            return;
+        }
+        let attacker_to_move = position.side_to_move() == self.attacker;
+        let children = self.nodes[node]
+            .children
+            .iter()
+            .map(|&child| &self.nodes[child]);
+        let min_proof = children.clone().map(|child| child.proof).min().unwrap();
+        let min_disproof = children.clone().map(|child| child.disproof).min().unwrap();
+        let sum_proof = children
+            .clone()
+            .fold(0, |sum: u64, child| sum.saturating_add(child.proof));
+        let sum_disproof = children.fold(0, |sum: u64, child| sum.saturating_add(child.disproof));
+        let node = &mut self.nodes[node];
+        if attacker_to_move {
+            node.proof = min_proof;
+            node.disproof = sum_disproof;
+        } else {
+            node.proof = sum_proof;
+            node.disproof = min_disproof;
+        }
+    }
+
+    /// Extracts the proof (or disproof) of a solved node.
+    fn proof_tree<P: Position<Move = M>>(&self, node: usize, position: &mut P) -> ProofTree<M> {
+        let proven = self.nodes[node].proof == 0;
+        let attacker_to_move = position.side_to_move() == self.attacker;
+        let mut solved_children = self.nodes[node].children.iter().filter(|&&child| {
+            if proven {
+                self.nodes[child].proof == 0
+            } else {
+                self.nodes[child].disproof == 0
+            }
+        });
+        // The side that is winning the goal only needs one move, the other side needs all of them
+        let children: Vec<usize> = if proven == attacker_to_move {
+            solved_children.next().into_iter().copied().collect()
+        } else {
+            solved_children.copied().collect()
+        };
+        ProofTree {
+            children: children
+                .into_iter()
+                .map(|child| {
+                    let mv = self.nodes[child].mv.clone().unwrap();
+                    let reverse_move = position.do_move(mv.clone());
+                    let subtree = self.proof_tree(child, position);
+                    position.reverse_move(reverse_move);
+                    (mv, subtree)
+                })
+                .collect(),
+        }
+    }
+}
+
+struct Dfpn<P: HashablePosition> {
+    /// Proof and disproof numbers, keyed by the position and, for games with a maximum length, its ply count.
+    table: HashMap<(P::HashPosition, u32), (u64, u64)>,
+    attacker: Color,
+    goal: Goal,
+    nodes: usize,
+    max_nodes: usize,
+}
+
+impl<P: HashablePosition> Dfpn<P> {
+    fn key(position: &P, depth: u32) -> (P::HashPosition, u32) {
+        let depth = if P::MAX_GAME_LENGTH.is_some() {
+            depth
+        } else {
+            0
+        };
+        (position.hash_position(), depth)
+    }
+
+    /// Returns the proof and disproof numbers of a position `depth` plies below the root, without searching it.
+    fn evaluate(&self, position: &P, depth: u32) -> (u64, u64) {
+        let result = position.game_result().or_else(|| {
+            if P::MAX_GAME_LENGTH.is_some_and(|length| depth >= length) {
+                Some(GameResult::Draw)
+            } else {
+                None
+            }
+        });
+        match result {
+            Some(result) if self.goal.is_proven_by(result, self.attacker) => (0, INFINITY),
+            Some(_) => (INFINITY, 0),
+            None => self
+                .table
+                .get(&Self::key(position, depth))
+                .copied()
+                .unwrap_or((1, 1)),
+        }
+    }
+
+    /// Searches the position until its proof number reaches `max_proof`, or its disproof number reaches `max_disproof`, or the node limit is reached. Returns its proof and disproof numbers.
+    fn mid(
+        &mut self,
+        position: &mut P,
+        depth: u32,
+        max_proof: u64,
+        max_disproof: u64,
+    ) -> (u64, u64) {
+        let numbers = self.evaluate(position, depth);
+        if numbers.0 == 0 || numbers.1 == 0 {
+            return numbers;
+        }
+        let mut moves = vec![];
+        position.generate_moves(&mut moves);
+        if moves.is_empty() {
+            // The game is not decided, but there are no legal moves. Treat it as a draw.
+            let numbers = if self.goal.is_proven_by(GameResult::Draw, self.attacker) {
+                (0, INFINITY)
+            } else {
+                (INFINITY, 0)
+            };
+            self.table.insert(Self::key(position, depth), numbers);
+            return numbers;
+        }
+        let attacker_to_move = position.side_to_move() == self.attacker;
+        let mut children = Vec::with_capacity(moves.len());
+        loop {
+            self.nodes += 1;
+            children.clear();
+            for mv in moves.iter() {
+                let reverse_move = position.do_move(mv.clone());
+                children.push(self.evaluate(position, depth + 1));
+                position.reverse_move(reverse_move);
+            }
+            // Work with the numbers for the side to move: proof numbers at attacker nodes, disproof numbers at defender nodes
+            let (phi, delta): (Vec<u64>, Vec<u64>) = if attacker_to_move {
+                children.iter().copied().unzip()
+            } else {
+                children
+                    .iter()
+                    .map(|&(proof, disproof)| (disproof, proof))
+                    .unzip()
+            };
+            let min_phi = *phi.iter().min().unwrap();
+            let sum_delta = delta
+                .iter()
+                .fold(0, |sum: u64, &delta| sum.saturating_add(delta));
+            let numbers = if attacker_to_move {
+                (min_phi, sum_delta)
+            } else {
+                (sum_delta, min_phi)
+            };
+            self.table.insert(Self::key(position, depth), numbers);
+            let (max_phi, max_delta) = if attacker_to_move {
+                (max_proof, max_disproof)
+            } else {
+                (max_disproof, max_proof)
+            };
+            if min_phi >= max_phi || sum_delta >= max_delta || self.nodes >= self.max_nodes {
+                return numbers;
+            }
+
+            let best = (0..moves.len()).min_by_key(|&i| phi[i]).unwrap();
+            let second_phi = (0..moves.len())
+                .filter(|&i| i != best)
+                .map(|i| phi[i])
+                .min()
+                .unwrap_or(INFINITY);
+            // Search the best child until it is no longer the best, or the node's own thresholds would be reached
+            let child_max_phi = max_phi.min(second_phi.saturating_add(1));
+            let child_max_delta = if max_delta == INFINITY {
+                INFINITY
+            } else {
+                max_delta - sum_delta + delta[best]
+            };
+            let (child_max_proof, child_max_disproof) = if attacker_to_move {
+                (child_max_phi, child_max_delta)
+            } else {
+                (child_max_delta, child_max_phi)
+            };
+            let reverse_move = position.do_move(moves[best].clone());
+            self.mid(position, depth + 1, child_max_proof, child_max_disproof);
+            position.reverse_move(reverse_move);
+        }
+    }
+
+    /// Extracts the proof (or disproof) of a solved position from the transposition table.
+    fn proof_tree(&self, position: &mut P, depth: u32, proven: bool) -> ProofTree<P::Move> {
+        let mut moves = vec![];
+        if position.game_result().is_none()
+            && P::MAX_GAME_LENGTH.is_none_or(|length| depth < length)
+        {
+            position.generate_moves(&mut moves);
+        }
+        let attacker_to_move = position.side_to_move() == self.attacker;
+        let mut solved_children = moves.into_iter().filter(|mv| {
+            let reverse_move = position.do_move(mv.clone());
+            let (proof, disproof) = self.evaluate(position, depth + 1);
+            position.reverse_move(reverse_move);
+            if proven {
+                proof == 0
+            } else {
+                disproof == 0
+            }
+        });
+        // The side that is winning the goal only needs one move, the other side needs all of them
+        let children: Vec<P::Move> = if proven == attacker_to_move {
+            solved_children.next().into_iter().collect()
+        } else {
+            solved_children.collect()
+        };
+        ProofTree {
+            children: children
+                .into_iter()
+                .map(|mv| {
+                    let reverse_move = position.do_move(mv.clone());
+                    let subtree = self.proof_tree(position, depth + 1, proven);
+                    position.reverse_move(reverse_move);
+                    (mv, subtree)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Nim;
+
+    /// Nim, where the game is adjudicated as a draw after `LENGTH` plies.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct LimitedNim<const LENGTH: u32>(Nim);
+
+    impl<const LENGTH: u32> Position for LimitedNim<LENGTH> {
+        type Move = u32;
+        type ReverseMove = u32;
+        type Settings = ();
+
+        fn start_position_with_settings(settings: &()) -> Self {
+            LimitedNim(Nim::start_position_with_settings(settings))
+        }
+
+        fn side_to_move(&self) -> Color {
+            self.0.side_to_move()
+        }
+
+        fn generate_moves<E: Extend<u32>>(&self, moves: &mut E) {
+            self.0.generate_moves(moves)
+        }
+
+        fn do_move(&mut self, mv: u32) -> u32 {
+            self.0.do_move(mv)
+        }
+
+        fn reverse_move(&mut self, mv: u32) {
+            self.0.reverse_move(mv)
+        }
+
+        fn game_result(&self) -> Option<GameResult> {
+            self.0.game_result()
+        }
+
+        const MAX_GAME_LENGTH: Option<u32> = Some(LENGTH);
+    }
+
+    impl<const LENGTH: u32> HashablePosition for LimitedNim<LENGTH> {
+        type HashPosition = Nim;
+
+        fn hash_position(&self) -> Nim {
+            self.0
+        }
+    }
+
+    /// The side to move in Nim wins exactly when the number of stones is not a multiple of 4.
+    fn nim_value(position: &Nim) -> GameResult {
+        if position.0.is_multiple_of(4) {
+            GameResult::win_by(!position.1)
+        } else {
+            GameResult::win_by(position.1)
+        }
+    }
+
+    /// Checks that the tree proves `result` from the position: the winning side plays a single move, the losing side plays every legal move, and every leaf has the result.
+    /// Leaves that were solved elsewhere in the tree are only allowed if `transpositions` is set, and are not checked.
+    fn check_proof_tree<P: Position>(
+        position: &mut P,
+        tree: &ProofTree<P::Move>,
+        result: GameResult,
+        depth: u32,
+        transpositions: bool,
+    ) {
+        if tree.children.is_empty() {
+            if transpositions && position.game_result().is_none() {
+                return;
+            }
+            let leaf_result = position.game_result().unwrap_or(GameResult::Draw);
+            assert_eq!(leaf_result, result, "Wrong result at a leaf");
+            if position.game_result().is_none() {
+                assert!(P::MAX_GAME_LENGTH.is_some_and(|length| depth >= length));
+            }
+            return;
+        }
+        let mut moves = vec![];
+        position.generate_moves(&mut moves);
+        if result == GameResult::win_by(position.side_to_move()) {
+            assert_eq!(tree.children.len(), 1);
+        } else if result == GameResult::win_by(!position.side_to_move()) {
+            let tree_moves: Vec<P::Move> = tree.children.iter().map(|(mv, _)| mv.clone()).collect();
+            assert_eq!(tree_moves, moves);
+        }
+        for (mv, subtree) in tree.children.iter() {
+            assert!(moves.contains(mv));
+            let reverse_move = position.do_move(mv.clone());
+            check_proof_tree(position, subtree, result, depth + 1, transpositions);
+            position.reverse_move(reverse_move);
+        }
+    }
+
+    #[test]
+    fn solves_nim() {
+        for stones in 1..=12 {
+            for &color in Color::ALL.iter() {
+                let mut position = Nim(stones, color);
+                let expected = nim_value(&position);
+                for (solution, transpositions) in [
+                    (solve(&mut position, 100_000), false),
+                    (solve_with_transpositions(&mut position, 100_000), true),
+                    (solve_dfpn(&mut position, 100_000), false),
+                ]
+                .iter()
+                {
+                    assert_eq!(solution.result, Some(expected), "{:?}", position);
+                    let tree = solution.proof_tree.as_ref().unwrap();
+                    check_proof_tree(&mut position, tree, expected, 0, *transpositions);
+                    assert_eq!(position, Nim(stones, color));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn winning_move_is_in_the_proof_tree() {
+        let solution = solve(&mut Nim::start_position(), 100_000);
+        assert_eq!(solution.result, Some(GameResult::WhiteWin));
+        let tree = solution.proof_tree.unwrap();
+        assert_eq!(tree.children.len(), 1);
+        assert_eq!(tree.children[0].0, 2);
+
+        // With 8 stones, the side to move loses, so the proof tree holds every one of its moves
+        let solution = solve_dfpn(&mut Nim(8, Color::White), 100_000);
+        assert_eq!(solution.result, Some(GameResult::BlackWin));
+        assert_eq!(solution.proof_tree.unwrap().children.len(), 3);
+    }
+
+    #[test]
+    fn node_limit_gives_up() {
+        for solution in [
+            solve(&mut Nim(30, Color::White), 10),
+            solve_dfpn(&mut Nim(30, Color::White), 10),
+        ]
+        .iter()
+        {
+            assert_eq!(solution.result, None);
+            assert_eq!(solution.proof_tree, None);
+            assert!(solution.nodes > 0);
+        }
+    }
+
+    #[test]
+    fn max_game_length_forces_draws() {
+        // From 5 stones, white wins by leaving 4, which takes 3 plies
+        let mut position = LimitedNim::<3>(Nim(5, Color::White));
+        for solution in [
+            solve(&mut position, 100_000),
+            solve_dfpn(&mut position, 100_000),
+        ]
+        .iter()
+        {
+            assert_eq!(solution.result, Some(GameResult::WhiteWin));
+            check_proof_tree(
+                &mut position,
+                solution.proof_tree.as_ref().unwrap(),
+                GameResult::WhiteWin,
+                0,
+                false,
+            );
+        }
+
+        let mut position = LimitedNim::<2>(Nim(5, Color::White));
+        for solution in [
+            solve(&mut position, 100_000),
+            solve_dfpn(&mut position, 100_000),
+        ]
+        .iter()
+        {
+            assert_eq!(solution.result, Some(GameResult::Draw));
+            check_proof_tree(
+                &mut position,
+                solution.proof_tree.as_ref().unwrap(),
+                GameResult::Draw,
+                0,
+                false,
+            );
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Statistics for match results: Elo differences with error bars, likelihood of superiority, and the sequential probability ratio test (SPRT).
+//!
+//! Match results are given as win/draw/loss counts from the perspective of the first side. Error bars and the SPRT use a normal approximation of the per-game score distribution, so they are only meaningful after a reasonable number of games.
+
+/// The result of a match, from the perspective of the first side.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MatchScore {
+    pub wins: u64,
+    pub draws: u64,
+    pub losses: u64,
+}
+
+impl MatchScore {
+    /// Returns the number of games played.
+    pub fn games(&self) -> u64 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+/// Converts an expected score between 0 and 1 to an Elo difference.
+pub fn elo_from_score(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// Converts an Elo difference to an expected score between 0 and 1.
+pub fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10.0_f64.powf(-elo / 400.0))
+}
+
+/// Returns the mean score per game, and the variance of the score of a single game.
+fn score_mean_and_variance(score: MatchScore) -> (f64, f64) {
+    let games = score.games() as f64;
+    let mean = (score.wins as f64 + score.draws as f64 / 2.0) / games;
+    let variance = (score.wins as f64 * (1.0 - mean).powi(2)
+        + score.draws as f64 * (0.5 - mean).powi(2)
+        + score.losses as f64 * mean.powi(2))
+        / games;
+    (mean, variance)
+}
+
+/// An Elo difference, with a 95% confidence interval.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EloEstimate {
+    pub elo: f64,
+    /// Half the width of the confidence interval, in Elo.
+    pub error_margin: f64,
+}
+
+/// Estimates the Elo difference from a match score, with a 95% confidence interval.
+///
+/// Returns None if no games were played, or if one side scored every point, since the difference is then unbounded.
+/// # Examples
+/// ```rust
+/// use board_game_traits::stats::elo_difference;
+/// use board_game_traits::stats::MatchScore;
+/// let estimate = elo_difference(MatchScore { wins: 60, draws: 20, losses: 20 }).unwrap();
+/// assert!((estimate.elo - 147.2).abs() < 0.1);
+/// assert!(estimate.error_margin > 50.0 && estimate.error_margin < 100.0);
+/// ```
+pub fn elo_difference(score: MatchScore) -> Option<EloEstimate> {
+    if score.games() == 0 || score.wins + score.draws == 0 || score.losses + score.draws == 0 {
+        return None;
+    }
+    let (mean, variance) = score_mean_and_variance(score);
+    let standard_error = (variance / score.games() as f64).sqrt();
+    // 1.96 standard errors on either side of the mean is the 95% confidence interval
+    let low = (mean - 1.96 * standard_error).max(f64::MIN_POSITIVE);
+    let high = (mean + 1.96 * standard_error).min(1.0 - f64::EPSILON);
+    Some(EloEstimate {
+        elo: elo_from_score(mean),
+        error_margin: (elo_from_score(high) - elo_from_score(low)) / 2.0,
+    })
+}
+
+/// Returns the likelihood of superiority: the probability that the first side is stronger, given its match score. Draws are ignored.
+pub fn likelihood_of_superiority(score: MatchScore) -> f64 {
+    if score.wins + score.losses == 0 {
+        return 0.5;
+    }
+    let wins = score.wins as f64;
+    let losses = score.losses as f64;
+    0.5 * (1.0 + erf((wins - losses) / (2.0 * (wins + losses)).sqrt()))
+}
+
+/// Approximates the error function, with a maximum error of 1.5e-7 (Abramowitz and Stegun 7.1.26).
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let polynomial = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let y = 1.0 - polynomial * (-x * x).exp();
+    if x < 0.0 {
+        -y
+    } else {
+        y
+    }
+}
+
+/// The state of a sequential probability ratio test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SprtStatus {
+    /// The Elo difference is at most `elo0`.
+    AcceptH0,
+    /// The Elo difference is at least `elo1`.
+    AcceptH1,
+    /// More games are needed.
+    Continue,
+}
+
+/// A sequential probability ratio test, between the hypotheses that the Elo difference is `elo0` (H0) and that it is `elo1` (H1).
+///
+/// `alpha` is the probability of accepting H1 when H0 is true, and `beta` is the probability of accepting H0 when H1 is true.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64, alpha: f64, beta: f64) -> Self {
+        Sprt {
+            elo0,
+            elo1,
+            alpha,
+            beta,
+        }
+    }
+
+    /// Returns the lower and upper bounds for the log-likelihood ratio. The test accepts H0 below the lower bound, and H1 above the upper bound.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Returns the log-likelihood ratio of H1 against H0, given the match score.
+    ///
+    /// Returns 0 if no games were played, or if every game had the same outcome, since the score's variance cannot be estimated.
+    pub fn llr(&self, score: MatchScore) -> f64 {
+        if score.games() == 0 {
+            return 0.0;
+        }
+        let (mean, variance) = score_mean_and_variance(score);
+        if variance == 0.0 {
+            return 0.0;
+        }
+        let score0 = score_from_elo(self.elo0);
+        let score1 = score_from_elo(self.elo1);
+        score.games() as f64 * (score1 - score0) * (2.0 * mean - score0 - score1) / (2.0 * variance)
+    }
+
+    /// Returns whether the test has concluded, given the match score.
+    /// # Examples
+    /// ```rust
+    /// use board_game_traits::stats::{Sprt, SprtStatus};
+    /// use board_game_traits::stats::MatchScore;
+    /// let sprt = Sprt::new(0.0, 10.0, 0.05, 0.05);
+    /// assert_eq!(sprt.status(MatchScore { wins: 30, draws: 40, losses: 30 }), SprtStatus::Continue);
+    /// assert_eq!(sprt.status(MatchScore { wins: 500, draws: 400, losses: 300 }), SprtStatus::AcceptH1);
+    /// ```
+    pub fn status(&self, score: MatchScore) -> SprtStatus {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            SprtStatus::AcceptH0
+        } else if llr >= upper {
+            SprtStatus::AcceptH1
+        } else {
+            SprtStatus::Continue
+        }
+    }
+}
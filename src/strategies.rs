@@ -0,0 +1,51 @@
+//! `proptest` strategies for reachable positions and legal move sequences. Available with the `proptest` feature.
+//!
+//! Positions are generated by playing random legal moves from the start position. Each move is chosen by a generated number, taken modulo the number of legal moves.
+//!
+//! Shrinking works on the generated numbers, not on the moves. It removes numbers from anywhere in the sequence, and makes individual numbers smaller, which usually picks a move earlier in move generation order.
+//! Since each number is interpreted in the position reached by the ones before it, removing or changing a number also changes every later move, so a shrunk case is not necessarily a subsequence of the original game.
+
+use crate::Position;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use std::fmt;
+
+/// Plays the move chosen by each number in turn, and returns the moves played.
+/// Stops early if the game is decided, or there are no legal moves.
+fn replay<P: Position>(choices: &[u32]) -> (P, Vec<P::Move>) {
+    let mut position = P::start_position();
+    let mut moves_played = vec![];
+    let mut moves = vec![];
+    for &choice in choices {
+        if position.game_result().is_some() {
+            break;
+        }
+        moves.clear();
+        position.generate_moves(&mut moves);
+        if moves.is_empty() {
+            break;
+        }
+        let mv = moves.swap_remove(choice as usize % moves.len());
+        position.do_move(mv.clone());
+        moves_played.push(mv);
+    }
+    (position, moves_played)
+}
+
+/// Generates legal move sequences of at most `max_depth` plies from the start position.
+/// Sequences are shorter if the game ends first.
+pub fn move_sequence<P: Position>(max_depth: usize) -> impl Strategy<Value = Vec<P::Move>> {
+    vec(any::<u32>(), 0..=max_depth).prop_map(|choices| replay::<P>(&choices).1)
+}
+
+/// Generates positions reachable in at most `max_depth` plies from the start position.
+pub fn position<P: Position + fmt::Debug>(max_depth: usize) -> impl Strategy<Value = P> {
+    vec(any::<u32>(), 0..=max_depth).prop_map(|choices| replay::<P>(&choices).0)
+}
+
+/// Generates positions reachable in at most `max_depth` plies from the start position, together with the moves that lead to them.
+pub fn position_with_moves<P: Position + fmt::Debug>(
+    max_depth: usize,
+) -> impl Strategy<Value = (P, Vec<P::Move>)> {
+    vec(any::<u32>(), 0..=max_depth).prop_map(|choices| replay::<P>(&choices))
+}
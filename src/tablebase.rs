@@ -0,0 +1,443 @@
+//! Endgame tablebases, built by retrograde analysis.
+//!
+//! A tablebase holds the game-theoretic value of every position in an index space defined by the game, such as all positions with a given set of pieces, together with the number of plies to the end of the game with best play.
+
+use crate::{Color, GameResult, Position, RetrogradePosition};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+
+/// A game position that can be mapped to and from a dense range of indices.
+///
+/// For games that implement `SymmetricPosition`, the index space can cover only canonical positions, with `to_index` canonicalizing the position before indexing it. This shrinks the tablebase by up to the number of symmetries, and `build` and `probe` then work unchanged.
+pub trait IndexedPosition: Position {
+    /// Returns the size of the index space. Every index is smaller than this.
+    fn num_indices() -> usize;
+
+    /// Returns the index of the position, or None if it is outside the index space.
+    fn to_index(&self) -> Option<usize>;
+
+    /// Returns the position with the given index, or None if the index does not represent a valid position.
+    /// For every valid index, `from_index(index).to_index() == Some(index)`.
+    fn from_index(index: usize) -> Option<Self>;
+}
+
+/// The game-theoretic value of a position, from the perspective of the side to move.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TbResult {
+    /// The side to move wins, in this many plies with best play.
+    Win(u16),
+    /// The side to move loses, in this many plies with best play.
+    Loss(u16),
+    Draw,
+}
+
+impl TbResult {
+    /// Converts the value to a game result, given the side to move.
+    pub fn game_result(self, side_to_move: Color) -> GameResult {
+        match self {
+            TbResult::Win(_) => GameResult::win_by(side_to_move),
+            TbResult::Loss(_) => GameResult::win_by(!side_to_move),
+            TbResult::Draw => GameResult::Draw,
+        }
+    }
+}
+
+// Each entry is 0 for invalid indices, 1 for draws, 2 + 2n for a win in n plies and 3 + 2n for a loss in n plies.
+const INVALID: u16 = 0;
+const DRAW: u16 = 1;
+const MAGIC: &[u8; 4] = b"BGTB";
+const FORMAT_VERSION: u8 = 1;
+
+fn encode(result: TbResult) -> u16 {
+    match result {
+        TbResult::Draw => DRAW,
+        TbResult::Win(plies) => 2 + 2 * plies,
+        TbResult::Loss(plies) => 3 + 2 * plies,
+    }
+}
+
+fn decode(entry: u16) -> Option<TbResult> {
+    match entry {
+        INVALID => None,
+        DRAW => Some(TbResult::Draw),
+        _ if entry & 1 == 0 => Some(TbResult::Win((entry - 2) / 2)),
+        _ => Some(TbResult::Loss((entry - 3) / 2)),
+    }
+}
+
+/// The error returned when building a tablebase whose longest win is too long to store.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DistanceOverflowError;
+
+impl fmt::Display for DistanceOverflowError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt.write_str("distance to the end of the game is too long to store in the tablebase")
+    }
+}
+
+impl std::error::Error for DistanceOverflowError {}
+
+/// A complete tablebase for an index space, using two bytes per index.
+pub struct Tablebase<P> {
+    entries: Vec<u16>,
+    phantom: PhantomData<fn() -> P>,
+}
+
+impl<P> fmt::Debug for Tablebase<P> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt.debug_struct("Tablebase")
+            .field("num_indices", &self.entries.len())
+            .finish()
+    }
+}
+
+impl<P: IndexedPosition> Tablebase<P> {
+    /// Returns the tablebase's value for the position, or None if the position is outside the index space.
+    pub fn probe(&self, position: &P) -> Option<TbResult> {
+        self.entries
+            .get(position.to_index()?)
+            .copied()
+            .and_then(decode)
+    }
+
+    /// Returns the tablebase's value for the index, or None if the index is outside the index space or does not represent a valid position.
+    pub fn probe_index(&self, index: usize) -> Option<TbResult> {
+        self.entries.get(index).copied().and_then(decode)
+    }
+
+    /// Returns the size of the index space.
+    pub fn num_indices(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Writes the tablebase in a compact binary format.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION])?;
+        writer.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+        let bytes: Vec<u8> = self
+            .entries
+            .iter()
+            .flat_map(|entry| entry.to_le_bytes())
+            .collect();
+        writer.write_all(&bytes)
+    }
+
+    /// Reads a tablebase written by `write_to`. Fails if the tablebase does not match the size of `P`'s index space, or if there is any data after it.
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let invalid_data = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut header = [0; 13];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != MAGIC {
+            return Err(invalid_data("not a tablebase file"));
+        }
+        if header[4] != FORMAT_VERSION {
+            return Err(invalid_data("unsupported tablebase format version"));
+        }
+        let mut len_bytes = [0; 8];
+        len_bytes.copy_from_slice(&header[5..13]);
+        let len = u64::from_le_bytes(len_bytes) as usize;
+        if len != P::num_indices() {
+            return Err(invalid_data(
+                "tablebase size does not match the index space",
+            ));
+        }
+        let mut bytes = vec![0; len * 2];
+        reader.read_exact(&mut bytes)?;
+        if reader.read(&mut [0])? != 0 {
+            return Err(invalid_data("unexpected data after the tablebase"));
+        }
+        Ok(Tablebase {
+            entries: bytes
+                .chunks_exact(2)
+                .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+                .collect(),
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<P: IndexedPosition + RetrogradePosition> Tablebase<P> {
+    /// Builds the tablebase for the whole index space by retrograde analysis.
+    ///
+    /// Positions whose outcome depends on moves that leave the index space are never marked as lost, but may be marked as won through moves within it. Positions without a result that have no legal moves are draws.
+    pub fn build() -> Result<Self, DistanceOverflowError> {
+        let num_indices = P::num_indices();
+        let mut entries = vec![INVALID; num_indices];
+        // For unresolved positions, the number of moves that have not yet been shown to lead to a win for the opponent
+        let mut moves_left = vec![0u32; num_indices];
+        let mut resolved = vec![false; num_indices];
+        let mut queue = VecDeque::new();
+
+        let mut moves = vec![];
+        for index in 0..num_indices {
+            let mut position = match P::from_index(index) {
+                Some(position) => position,
+                None => continue,
+            };
+            if let Some(result) = position.game_result() {
+                let side_to_move = position.side_to_move();
+                let value = if result == GameResult::Draw {
+                    TbResult::Draw
+                } else if result == GameResult::win_by(side_to_move) {
+                    TbResult::Win(0)
+                } else {
+                    TbResult::Loss(0)
+                };
+                entries[index] = encode(value);
+                resolved[index] = true;
+                if value != TbResult::Draw {
+                    queue.push_back(index);
+                }
+                continue;
+            }
+            moves.clear();
+            position.generate_moves(&mut moves);
+            if moves.is_empty() {
+                entries[index] = DRAW;
+                resolved[index] = true;
+                continue;
+            }
+            entries[index] = DRAW;
+            moves_left[index] = moves.len() as u32;
+            for mv in moves.drain(..) {
+                let reverse_move = position.do_move(mv);
+                if position.to_index().is_none() {
+                    // This move leaves the index space, so the position can never be shown to be lost
+                    moves_left[index] = u32::MAX;
+                }
+                position.reverse_move(reverse_move);
+            }
+        }
+
+        let mut predecessors = vec![];
+        while let Some(index) = queue.pop_front() {
+            let value = decode(entries[index]).unwrap();
+            let position = P::from_index(index).unwrap();
+            predecessors.clear();
+            position.generate_unmoves(&mut predecessors);
+            for (predecessor, _) in predecessors.drain(..) {
+                let predecessor_index = match predecessor.to_index() {
+                    Some(predecessor_index) if !resolved[predecessor_index] => predecessor_index,
+                    _ => continue,
+                };
+                let predecessor_value = match value {
+                    TbResult::Loss(plies) => TbResult::Win(increment(plies)?),
+                    TbResult::Win(plies) => {
+                        let left = &mut moves_left[predecessor_index];
+                        if *left == u32::MAX {
+                            continue;
+                        }
+                        *left -= 1;
+                        if *left > 0 {
+                            continue;
+                        }
+                        TbResult::Loss(increment(plies)?)
+                    }
+                    TbResult::Draw => unreachable!(),
+                };
+                entries[predecessor_index] = encode(predecessor_value);
+                resolved[predecessor_index] = true;
+                queue.push_back(predecessor_index);
+            }
+        }
+
+        Ok(Tablebase {
+            entries,
+            phantom: PhantomData,
+        })
+    }
+}
+
+fn increment(plies: u16) -> Result<u16, DistanceOverflowError> {
+    // Wins and losses are stored as 2 + 2n and 3 + 2n, so n must leave room for that
+    if plies >= (u16::MAX - 3) / 2 {
+        Err(DistanceOverflowError)
+    } else {
+        Ok(plies + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Nim;
+
+    const MAX_STONES: u32 = 20;
+
+    impl IndexedPosition for Nim {
+        fn num_indices() -> usize {
+            2 * (MAX_STONES as usize + 1)
+        }
+
+        fn to_index(&self) -> Option<usize> {
+            if self.0 <= MAX_STONES {
+                Some(2 * self.0 as usize + self.1.disc())
+            } else {
+                None
+            }
+        }
+
+        fn from_index(index: usize) -> Option<Self> {
+            if index < Self::num_indices() {
+                Some(Nim(index as u32 / 2, Color::from_disc(index % 2)?))
+            } else {
+                None
+            }
+        }
+    }
+
+    impl RetrogradePosition for Nim {
+        fn generate_unmoves<E: Extend<(Self, u32)>>(&self, predecessors: &mut E) {
+            predecessors.extend((1..=3).map(|mv| (Nim(self.0 + mv, !self.1), mv)));
+        }
+    }
+
+    /// Returns the value of the position for the side to move, by searching the whole game tree.
+    fn minimax(position: &mut Nim) -> TbResult {
+        if position.game_result().is_some() {
+            return TbResult::Loss(0);
+        }
+        let mut moves = vec![];
+        position.generate_moves(&mut moves);
+        let values: Vec<TbResult> = moves
+            .into_iter()
+            .map(|mv| {
+                let reverse_move = position.do_move(mv);
+                let value = minimax(position);
+                position.reverse_move(reverse_move);
+                value
+            })
+            .collect();
+        let fastest_win = values
+            .iter()
+            .filter_map(|value| match value {
+                TbResult::Loss(plies) => Some(plies + 1),
+                _ => None,
+            })
+            .min();
+        let slowest_loss = values
+            .iter()
+            .filter_map(|value| match value {
+                TbResult::Win(plies) => Some(plies + 1),
+                _ => None,
+            })
+            .max();
+        match fastest_win {
+            Some(plies) => TbResult::Win(plies),
+            None => TbResult::Loss(slowest_loss.unwrap()),
+        }
+    }
+
+    #[test]
+    fn retrograde_analysis_matches_minimax() {
+        let tablebase = Tablebase::<Nim>::build().unwrap();
+        assert_eq!(tablebase.num_indices(), 42);
+        for index in 0..tablebase.num_indices() {
+            let mut position = Nim::from_index(index).unwrap();
+            assert_eq!(
+                tablebase.probe(&position),
+                Some(minimax(&mut position)),
+                "{:?}",
+                position
+            );
+        }
+        assert_eq!(
+            tablebase.probe(&Nim(8, Color::White)),
+            Some(TbResult::Loss(4))
+        );
+        assert_eq!(
+            tablebase.probe(&Nim(10, Color::Black)),
+            Some(TbResult::Win(5))
+        );
+        assert_eq!(tablebase.probe(&Nim(MAX_STONES + 1, Color::White)), None);
+        assert_eq!(tablebase.probe_index(42), None);
+    }
+
+    #[test]
+    fn results_are_converted_for_the_side_to_move() {
+        assert_eq!(
+            TbResult::Win(3).game_result(Color::Black),
+            GameResult::BlackWin
+        );
+        assert_eq!(
+            TbResult::Loss(0).game_result(Color::Black),
+            GameResult::WhiteWin
+        );
+        assert_eq!(TbResult::Draw.game_result(Color::White), GameResult::Draw);
+    }
+
+    #[test]
+    fn encoding_roundtrips() {
+        for &value in [
+            TbResult::Draw,
+            TbResult::Win(0),
+            TbResult::Loss(0),
+            TbResult::Win(1000),
+            TbResult::Loss(77),
+        ]
+        .iter()
+        {
+            assert_eq!(decode(encode(value)), Some(value));
+        }
+        assert_eq!(decode(INVALID), None);
+        assert!(increment((u16::MAX - 3) / 2).is_err());
+    }
+
+    fn tablebase_bytes() -> Vec<u8> {
+        let mut bytes = vec![];
+        Tablebase::<Nim>::build()
+            .unwrap()
+            .write_to(&mut bytes)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn file_roundtrips() {
+        let bytes = tablebase_bytes();
+        assert_eq!(bytes.len(), 13 + 2 * 42);
+        let tablebase = Tablebase::<Nim>::read_from(&bytes[..]).unwrap();
+        let original = Tablebase::<Nim>::build().unwrap();
+        for index in 0..42 {
+            assert_eq!(tablebase.probe_index(index), original.probe_index(index));
+        }
+    }
+
+    #[test]
+    fn truncated_files_are_rejected() {
+        let bytes = tablebase_bytes();
+        for len in [0, 4, 12, 13, bytes.len() - 1].iter() {
+            let error = Tablebase::<Nim>::read_from(&bytes[..*len]).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof, "{} bytes", len);
+        }
+    }
+
+    #[test]
+    fn oversized_files_are_rejected() {
+        let mut bytes = tablebase_bytes();
+        bytes.push(0);
+        let error = Tablebase::<Nim>::read_from(&bytes[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // A header for a larger index space is rejected before reading the entries
+        let mut bytes = tablebase_bytes();
+        bytes[5..13].copy_from_slice(&43u64.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        let error = Tablebase::<Nim>::read_from(&bytes[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn other_files_are_rejected() {
+        let mut bytes = tablebase_bytes();
+        bytes[0] = b'X';
+        assert!(Tablebase::<Nim>::read_from(&bytes[..]).is_err());
+        let mut bytes = tablebase_bytes();
+        bytes[4] = FORMAT_VERSION + 1;
+        assert!(Tablebase::<Nim>::read_from(&bytes[..]).is_err());
+    }
+}
@@ -0,0 +1,491 @@
+//! Checks of the documented trait contracts, for use in the test suites of `Position` implementations. Available with the `test-utils` feature.
+//!
+//! The checks are run on single positions, and `play_random_games` runs them on every position in a number of random games. `assert_position_laws` combines everything:
+//! ```rust
+//! use board_game_traits::testing::{assert_position_laws, fuzz_move_is_legal};
+//! use board_game_traits::{Color, GameResult, Position};
+//! use rand::SeedableRng;
+//!
+//! #[derive(Clone, Debug, PartialEq)]
+//! struct Nim(u32, Color);
+//!
+//! impl Position for Nim {
+//!     type Move = u32;
+//!     type ReverseMove = u32;
+//!     type Settings = ();
+//!     fn start_position_with_settings(_: &()) -> Self { Nim(10, Color::White) }
+//!     fn side_to_move(&self) -> Color { self.1 }
+//!     fn generate_moves<E: Extend<u32>>(&self, moves: &mut E) { moves.extend(1..=self.0.min(3)) }
+//!     fn do_move(&mut self, mv: u32) -> u32 { self.0 -= mv; self.1 = !self.1; mv }
+//!     fn reverse_move(&mut self, mv: u32) { self.0 += mv; self.1 = !self.1 }
+//!     fn game_result(&self) -> Option<GameResult> { if self.0 == 0 { Some(GameResult::win_by(!self.1)) } else { None } }
+//! }
+//!
+//! let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+//! assert_position_laws::<Nim, _>(&mut rng, 100, 200);
+//! fuzz_move_is_legal::<Nim, _>(&mut rng, 20, 200, 4);
+//! ```
+
+use crate::{
+    Color, CompressedMove, EvalPosition, GameResult, HashablePosition, Position, ProgressCounter,
+};
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::Hash;
+
+/// A minimal game, for testing generic code and for examples: starting from 10 stones, the players take turns removing 1 to 3 stones, and whoever takes the last stone wins.
+///
+/// Holds the number of stones left, and the side to move. Moves are the number of stones to take.
+/// # Examples
+/// ```rust
+/// use board_game_traits::testing::Nim;
+/// use board_game_traits::{Color, GameResult, Position};
+/// let mut position = Nim::start_position();
+/// for mv in [3, 3, 3, 1].iter() {
+///     position.do_move(*mv);
+/// }
+/// assert_eq!(position, Nim(0, Color::White));
+/// assert_eq!(position.game_result(), Some(GameResult::BlackWin));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Nim(pub u32, pub Color);
+
+impl Position for Nim {
+    type Move = u32;
+    type ReverseMove = u32;
+    type Settings = ();
+
+    fn start_position_with_settings(_settings: &()) -> Self {
+        Nim(10, Color::White)
+    }
+
+    fn side_to_move(&self) -> Color {
+        self.1
+    }
+
+    fn generate_moves<E: Extend<u32>>(&self, moves: &mut E) {
+        moves.extend(1..=self.0.min(3))
+    }
+
+    fn do_move(&mut self, mv: u32) -> u32 {
+        self.0 -= mv;
+        self.1 = !self.1;
+        mv
+    }
+
+    fn reverse_move(&mut self, mv: u32) {
+        self.0 += mv;
+        self.1 = !self.1;
+    }
+
+    fn game_result(&self) -> Option<GameResult> {
+        if self.0 == 0 {
+            Some(GameResult::win_by(!self.1))
+        } else {
+            None
+        }
+    }
+}
+
+/// Leaving a multiple of 4 stones wins, so the evaluation is perfect.
+impl EvalPosition for Nim {
+    fn static_eval(&self) -> f32 {
+        if self.0.is_multiple_of(4) {
+            -self.1.multiplier() as f32
+        } else {
+            self.1.multiplier() as f32
+        }
+    }
+}
+
+impl HashablePosition for Nim {
+    type HashPosition = Nim;
+
+    fn hash_position(&self) -> Nim {
+        *self
+    }
+}
+
+impl CompressedMove for Nim {
+    fn compress(mv: &u32) -> u32 {
+        *mv
+    }
+
+    fn decompress(&self, compressed: u32) -> Option<u32> {
+        Some(compressed).filter(|mv| (1..=self.0.min(3)).contains(mv))
+    }
+}
+
+/// A minimal game that never ends by itself, for testing repetition and no-progress detection: the players take turns moving a token one square left or right on an endless line, or marking the token's square.
+///
+/// Moves are -1 and 1 to move the token, and 0 to mark its square. Marks are never removed, so marking is irreversible.
+/// # Examples
+/// ```rust
+/// use board_game_traits::testing::Walk;
+/// use board_game_traits::{Position, ProgressCounter};
+/// let mut position = Walk::start_position();
+/// for mv in [1, 1, 0, -1].iter() {
+///     position.do_move(*mv);
+/// }
+/// assert_eq!((position.square, position.marks, position.halfmove_clock()), (1, 1, 1));
+/// assert!(!position.is_irreversible(&1));
+/// assert!(position.is_irreversible(&0));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Walk {
+    /// The token's square.
+    pub square: i32,
+    /// The number of marks made.
+    pub marks: u32,
+    /// The number of plies since the last mark.
+    pub plies_since_mark: u32,
+    /// The side to move.
+    pub side_to_move: Color,
+}
+
+impl Position for Walk {
+    type Move = i32;
+    /// The move, and the plies since the last mark before it.
+    type ReverseMove = (i32, u32);
+    type Settings = ();
+
+    fn start_position_with_settings(_settings: &()) -> Self {
+        Walk {
+            square: 0,
+            marks: 0,
+            plies_since_mark: 0,
+            side_to_move: Color::White,
+        }
+    }
+
+    fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
+
+    fn generate_moves<E: Extend<i32>>(&self, moves: &mut E) {
+        moves.extend([-1, 0, 1].iter().copied())
+    }
+
+    fn do_move(&mut self, mv: i32) -> (i32, u32) {
+        let reverse_move = (mv, self.plies_since_mark);
+        if mv == 0 {
+            self.marks += 1;
+            self.plies_since_mark = 0;
+        } else {
+            self.square += mv;
+            self.plies_since_mark += 1;
+        }
+        self.side_to_move = !self.side_to_move;
+        reverse_move
+    }
+
+    fn reverse_move(&mut self, (mv, plies_since_mark): (i32, u32)) {
+        if mv == 0 {
+            self.marks -= 1;
+        } else {
+            self.square -= mv;
+        }
+        self.plies_since_mark = plies_since_mark;
+        self.side_to_move = !self.side_to_move;
+    }
+
+    fn game_result(&self) -> Option<GameResult> {
+        None
+    }
+}
+
+/// The clock is not part of the position, so positions that only differ in their clock are repetitions.
+impl HashablePosition for Walk {
+    type HashPosition = (i32, u32, Color);
+
+    fn hash_position(&self) -> (i32, u32, Color) {
+        (self.square, self.marks, self.side_to_move)
+    }
+}
+
+impl ProgressCounter for Walk {
+    fn halfmove_clock(&self) -> u32 {
+        self.plies_since_mark
+    }
+
+    fn is_irreversible(&self, mv: &i32) -> bool {
+        *mv == 0
+    }
+}
+
+/// Plays `num_games` random games of at most `max_plies` plies from the start position, and calls `check` on every position along the way, including the start and final positions.
+/// Returns the total number of positions checked.
+pub fn play_random_games<P, R, F>(
+    rng: &mut R,
+    num_games: usize,
+    max_plies: u32,
+    mut check: F,
+) -> usize
+where
+    P: Position,
+    R: Rng,
+    F: FnMut(&mut P),
+{
+    let mut positions_checked = 0;
+    let mut moves = vec![];
+    for _ in 0..num_games {
+        let mut position = P::start_position();
+        for ply in 0..=max_plies {
+            check(&mut position);
+            positions_checked += 1;
+            if ply == max_plies || position.game_result().is_some() {
+                break;
+            }
+            moves.clear();
+            position.generate_moves(&mut moves);
+            if moves.is_empty() {
+                break;
+            }
+            let mv = moves.swap_remove(rng.gen_range(0..moves.len()));
+            position.do_move(mv);
+        }
+    }
+    positions_checked
+}
+
+/// Checks that doing and then undoing every legal move restores the position exactly.
+///
+/// # Panics
+///
+/// Panics if any move is not undone correctly.
+pub fn assert_do_undo_roundtrip<P>(position: &mut P)
+where
+    P: Position + Clone + PartialEq + fmt::Debug,
+{
+    let original = position.clone();
+    let mut moves = vec![];
+    position.generate_moves(&mut moves);
+    for mv in moves {
+        let reverse_move = position.do_move(mv.clone());
+        position.reverse_move(reverse_move);
+        assert_eq!(
+            *position, original,
+            "Doing and undoing {:?} changed the position",
+            mv
+        );
+    }
+}
+
+/// Checks that every generated move is accepted by `move_is_legal`, and that no move is generated twice.
+///
+/// # Panics
+///
+/// Panics if a generated move is rejected, or generated twice.
+pub fn assert_generated_moves_are_legal<P: Position>(position: &P) {
+    let mut moves = vec![];
+    position.generate_moves(&mut moves);
+    for (i, mv) in moves.iter().enumerate() {
+        assert!(
+            position.move_is_legal(mv.clone()),
+            "Generated move {:?} is not legal according to move_is_legal",
+            mv
+        );
+        assert!(
+            !moves[..i].contains(mv),
+            "Move {:?} was generated twice",
+            mv
+        );
+    }
+}
+
+/// Checks that `move_is_legal` accepts exactly the candidate moves that are generated in the position.
+///
+/// # Panics
+///
+/// Panics if `move_is_legal` disagrees with `generate_moves` for any candidate.
+pub fn assert_move_is_legal_agrees<P: Position>(position: &P, candidates: &[P::Move]) {
+    let mut moves = vec![];
+    position.generate_moves(&mut moves);
+    for mv in candidates {
+        let generated = moves.contains(mv);
+        assert_eq!(
+            position.move_is_legal(mv.clone()),
+            generated,
+            "move_is_legal returned {} for {:?}, but the move is {}generated",
+            !generated,
+            mv,
+            if generated { "" } else { "not " }
+        );
+    }
+}
+
+/// Fuzzes `move_is_legal` against `generate_moves` over random games.
+///
+/// First collects the distinct moves generated in `num_games` random games into a pool. Then, in `num_games` new random games, checks every position with its own legal moves, and with `samples_per_position` moves drawn from the pool, which are mostly illegal in that position.
+///
+/// # Panics
+///
+/// Panics if `move_is_legal` disagrees with `generate_moves` for any move.
+pub fn fuzz_move_is_legal<P, R>(
+    rng: &mut R,
+    num_games: usize,
+    max_plies: u32,
+    samples_per_position: usize,
+) where
+    P: Position,
+    P::Move: Hash,
+    R: Rng,
+{
+    let mut pool = vec![];
+    play_random_games(rng, num_games, max_plies, |position: &mut P| {
+        position.generate_moves(&mut pool);
+    });
+    // Remove duplicates while keeping the first occurrences in order, so that the pool is deterministic
+    let mut seen = HashSet::new();
+    pool.retain(|mv| seen.insert(mv.clone()));
+    if pool.is_empty() {
+        return;
+    }
+
+    let mut candidates = vec![];
+    let mut sample_rng = rand::rngs::SmallRng::seed_from_u64(rng.gen());
+    play_random_games(rng, num_games, max_plies, |position: &mut P| {
+        candidates.clear();
+        position.generate_moves(&mut candidates);
+        for _ in 0..samples_per_position {
+            candidates.push(pool[sample_rng.gen_range(0..pool.len())].clone());
+        }
+        assert_move_is_legal_agrees(position, &candidates);
+    });
+}
+
+/// Checks that the game result is deterministic, and is restored along with the position when a move is undone.
+///
+/// # Panics
+///
+/// Panics if the result changes between calls, or after doing and undoing a move.
+pub fn assert_game_result_consistency<P: Position>(position: &mut P) {
+    let result = position.game_result();
+    assert_eq!(
+        position.game_result(),
+        result,
+        "game_result returned different results for the same position"
+    );
+    let side_to_move = position.side_to_move();
+    let mut moves = vec![];
+    position.generate_moves(&mut moves);
+    for mv in moves {
+        let reverse_move = position.do_move(mv.clone());
+        position.reverse_move(reverse_move);
+        assert_eq!(
+            position.game_result(),
+            result,
+            "Doing and undoing {:?} changed the game result",
+            mv
+        );
+        assert_eq!(
+            position.side_to_move(),
+            side_to_move,
+            "Doing and undoing {:?} changed the side to move",
+            mv
+        );
+    }
+}
+
+/// Runs every check in this module on every position in `num_games` random games of at most `max_plies` plies.
+///
+/// # Panics
+///
+/// Panics if any check fails.
+pub fn assert_position_laws<P, R>(rng: &mut R, num_games: usize, max_plies: u32)
+where
+    P: Position + Clone + PartialEq + fmt::Debug,
+    R: Rng,
+{
+    play_random_games(rng, num_games, max_plies, |position: &mut P| {
+        assert_generated_moves_are_legal(position);
+        assert_do_undo_roundtrip(position);
+        assert_game_result_consistency(position);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+
+    #[test]
+    fn nim_obeys_position_laws() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        assert_position_laws::<Nim, _>(&mut rng, 20, 20);
+        fuzz_move_is_legal::<Nim, _>(&mut rng, 20, 20, 4);
+    }
+
+    #[test]
+    fn walk_obeys_position_laws() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        assert_position_laws::<Walk, _>(&mut rng, 10, 20);
+        fuzz_move_is_legal::<Walk, _>(&mut rng, 10, 20, 4);
+    }
+
+    #[test]
+    fn random_games_check_start_and_final_positions() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut finished = 0;
+        let positions_checked = play_random_games(&mut rng, 5, 20, |position: &mut Nim| {
+            if position.game_result().is_some() {
+                finished += 1;
+            }
+        });
+        // Every game ends within 10 plies, so every game is played to the end
+        assert_eq!(finished, 5);
+        assert!((5 * 5..=5 * 11).contains(&positions_checked));
+    }
+
+    #[test]
+    fn max_plies_cuts_games_short() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let positions_checked = play_random_games(&mut rng, 3, 2, |_: &mut Nim| ());
+        assert_eq!(positions_checked, 9);
+    }
+
+    #[test]
+    fn nim_eval_is_perfect() {
+        // White wins by leaving 8 stones, and every other move loses
+        let mut position = Nim::start_position();
+        assert!(position.static_eval() > 0.0);
+        for mv in 1..=3 {
+            let reverse_move = position.do_move(mv);
+            assert_eq!(position.static_eval() > 0.0, mv == 2);
+            position.reverse_move(reverse_move);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "generated twice")]
+    fn duplicate_moves_are_detected() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Duplicating(Nim);
+        impl Position for Duplicating {
+            type Move = u32;
+            type ReverseMove = u32;
+            type Settings = ();
+            fn start_position_with_settings(_: &()) -> Self {
+                Duplicating(Nim::start_position())
+            }
+            fn side_to_move(&self) -> Color {
+                self.0.side_to_move()
+            }
+            fn generate_moves<E: Extend<u32>>(&self, moves: &mut E) {
+                self.0.generate_moves(moves);
+                moves.extend(Some(1));
+            }
+            fn do_move(&mut self, mv: u32) -> u32 {
+                self.0.do_move(mv)
+            }
+            fn reverse_move(&mut self, mv: u32) {
+                self.0.reverse_move(mv)
+            }
+            fn game_result(&self) -> Option<GameResult> {
+                self.0.game_result()
+            }
+        }
+        assert_generated_moves_are_legal(&Duplicating::start_position());
+    }
+}
@@ -0,0 +1,308 @@
+//! Round-robin and gauntlet tournaments between several engines, built on the arena.
+//!
+//! Engines are given as factories, so that every game gets a fresh engine, and games can be played in parallel on several threads.
+
+use crate::arena::{self, ArenaGame, ArenaOptions};
+use crate::clock::{DefaultTimeSource, TimeSource};
+use crate::stats::MatchScore;
+use crate::{Engine, Position};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A function that creates a new instance of an engine.
+pub type EngineFactory<P> = Box<dyn Fn() -> Box<dyn Engine<P>> + Send + Sync>;
+
+/// Which engines play against each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TournamentFormat {
+    /// Every engine plays every other engine.
+    RoundRobin,
+    /// The first engine plays every other engine, and the others do not play each other.
+    Gauntlet,
+}
+
+/// Builds a `Tournament`.
+pub struct TournamentBuilder<P: Position> {
+    start_position: P,
+    options: ArenaOptions,
+    engines: Vec<(String, EngineFactory<P>)>,
+    format: TournamentFormat,
+    games_per_pairing: usize,
+    openings: Vec<Vec<P::Move>>,
+    threads: usize,
+    time_source: Arc<dyn TimeSource + Send + Sync>,
+}
+
+impl<P: Position> TournamentBuilder<P> {
+    /// Starts building a round-robin tournament with two games per pairing, played on a single thread.
+    pub fn new(start_position: P, options: ArenaOptions) -> Self {
+        TournamentBuilder {
+            start_position,
+            options,
+            engines: vec![],
+            format: TournamentFormat::RoundRobin,
+            games_per_pairing: 2,
+            openings: vec![],
+            threads: 1,
+            time_source: Arc::new(DefaultTimeSource::default()),
+        }
+    }
+
+    /// Adds an engine to the tournament. In a gauntlet, the first engine added plays every game.
+    pub fn engine<F>(mut self, name: &str, factory: F) -> Self
+    where
+        F: Fn() -> Box<dyn Engine<P>> + Send + Sync + 'static,
+    {
+        self.engines.push((name.to_string(), Box::new(factory)));
+        self
+    }
+
+    pub fn format(mut self, format: TournamentFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the number of games each pair of engines plays. The engines alternate colors, as in `arena::play_match`.
+    pub fn games_per_pairing(mut self, games_per_pairing: usize) -> Self {
+        self.games_per_pairing = games_per_pairing;
+        self
+    }
+
+    /// Sets the openings, as move lists from the start position. Each pairing uses them in the same order, as in `arena::play_match`.
+    pub fn openings(mut self, openings: Vec<Vec<P::Move>>) -> Self {
+        self.openings = openings;
+        self
+    }
+
+    /// Sets the number of games played in parallel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is zero.
+    pub fn threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "A tournament needs at least one thread");
+        self.threads = threads;
+        self
+    }
+
+    /// Sets the time source that measures the engines' thinking time. Defaults to `DefaultTimeSource`.
+    pub fn time_source<T>(mut self, time_source: T) -> Self
+    where
+        T: TimeSource + Send + Sync + 'static,
+    {
+        self.time_source = Arc::new(time_source);
+        self
+    }
+
+    pub fn build(self) -> Tournament<P> {
+        Tournament {
+            start_position: self.start_position,
+            options: self.options,
+            engines: self.engines,
+            format: self.format,
+            games_per_pairing: self.games_per_pairing,
+            openings: self.openings,
+            threads: self.threads,
+            time_source: self.time_source,
+        }
+    }
+}
+
+/// A tournament between several engines. Created with `TournamentBuilder`.
+pub struct Tournament<P: Position> {
+    start_position: P,
+    options: ArenaOptions,
+    engines: Vec<(String, EngineFactory<P>)>,
+    format: TournamentFormat,
+    games_per_pairing: usize,
+    openings: Vec<Vec<P::Move>>,
+    threads: usize,
+    time_source: Arc<dyn TimeSource + Send + Sync>,
+}
+
+impl<P> Tournament<P>
+where
+    P: Position + Clone + Sync,
+    P::Move: Send + Sync,
+{
+    /// Returns every pairing of engines, as indices in the order they were added.
+    pub fn pairings(&self) -> Vec<(usize, usize)> {
+        let num_engines = self.engines.len();
+        match self.format {
+            TournamentFormat::RoundRobin => (0..num_engines)
+                .flat_map(|first| (first + 1..num_engines).map(move |second| (first, second)))
+                .collect(),
+            TournamentFormat::Gauntlet => (1..num_engines).map(|second| (0, second)).collect(),
+        }
+    }
+
+    /// Plays every game in the tournament.
+    ///
+    /// Engines that crash, run out of time or play illegal moves forfeit the game, as in `arena::play_game`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the opening moves is illegal, or if an opening ends the game.
+    pub fn run(&self) -> TournamentResult<P::Move> {
+        let jobs: Vec<(usize, usize, usize)> = self
+            .pairings()
+            .into_iter()
+            .flat_map(|(first, second)| {
+                (0..self.games_per_pairing).map(move |game| (first, second, game))
+            })
+            .collect();
+        let next_job = AtomicUsize::new(0);
+        let games = Mutex::new(Vec::with_capacity(jobs.len()));
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(jobs.len()) {
+                scope.spawn(|| loop {
+                    let job_index = next_job.fetch_add(1, Ordering::Relaxed);
+                    let &(first, second, game) = match jobs.get(job_index) {
+                        Some(job) => job,
+                        None => break,
+                    };
+                    let game = self.play(first, second, game);
+                    games.lock().unwrap().push((job_index, game));
+                });
+            }
+        });
+
+        let mut games = games.into_inner().unwrap();
+        games.sort_by_key(|(job_index, _)| *job_index);
+        TournamentResult {
+            names: self.engines.iter().map(|(name, _)| name.clone()).collect(),
+            games: games.into_iter().map(|(_, game)| game).collect(),
+        }
+    }
+
+    fn play(&self, first: usize, second: usize, game: usize) -> TournamentGame<P::Move> {
+        let mut first_engine = (self.engines[first].1)();
+        let mut second_engine = (self.engines[second].1)();
+        let game = arena::play_match_game(
+            &mut *first_engine,
+            &mut *second_engine,
+            &self.start_position,
+            &self.openings,
+            game,
+            &self.options,
+            &*self.time_source,
+        );
+        TournamentGame {
+            first,
+            second,
+            game,
+        }
+    }
+}
+
+/// A game played in a tournament.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TournamentGame<M> {
+    /// The index of the first engine in the pairing.
+    pub first: usize,
+    /// The index of the second engine in the pairing.
+    pub second: usize,
+    pub game: ArenaGame<M>,
+}
+
+/// The games of a finished tournament.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TournamentResult<M> {
+    /// The engines' names, in the order they were added.
+    pub names: Vec<String>,
+    /// Every game, ordered by pairing.
+    pub games: Vec<TournamentGame<M>>,
+}
+
+impl<M> TournamentResult<M> {
+    /// Returns the score of `engine` against `opponent`.
+    pub fn head_to_head(&self, engine: usize, opponent: usize) -> MatchScore {
+        let mut score = MatchScore::default();
+        for game in self.games.iter() {
+            let first_score = game.game.first_engine_score();
+            if (game.first, game.second) == (engine, opponent) {
+                add_score(&mut score, first_score);
+            } else if (game.first, game.second) == (opponent, engine) {
+                add_score(
+                    &mut score,
+                    MatchScore {
+                        wins: first_score.losses,
+                        draws: first_score.draws,
+                        losses: first_score.wins,
+                    },
+                );
+            }
+        }
+        score
+    }
+
+    /// Returns the engine's total score over the tournament.
+    pub fn score(&self, engine: usize) -> MatchScore {
+        let mut score = MatchScore::default();
+        for opponent in (0..self.names.len()).filter(|&opponent| opponent != engine) {
+            add_score(&mut score, self.head_to_head(engine, opponent));
+        }
+        score
+    }
+
+    /// Returns the engines' indices, ordered by points, highest first.
+    pub fn standings(&self) -> Vec<usize> {
+        let mut standings: Vec<usize> = (0..self.names.len()).collect();
+        standings.sort_by(|&a, &b| points(self.score(b)).total_cmp(&points(self.score(a))));
+        standings
+    }
+
+    /// Formats a crosstable, with one row per engine ordered by points. Each column gives the row engine's points against the engine of that rank.
+    pub fn crosstable(&self) -> String {
+        let standings = self.standings();
+        let name_width = self.names.iter().map(|name| name.len()).max().unwrap_or(0);
+        let mut table = format!(
+            "{:>3}  {:<name_width$}  {:>6}  {:>5}",
+            "#",
+            "Name",
+            "Points",
+            "Games",
+            name_width = name_width
+        );
+        for rank in 1..=standings.len() {
+            write!(table, "  {:>5}", rank).unwrap();
+        }
+        for (rank, &engine) in standings.iter().enumerate() {
+            let score = self.score(engine);
+            write!(
+                table,
+                "\n{:>3}  {:<name_width$}  {:>6.1}  {:>5}",
+                rank + 1,
+                self.names[engine],
+                points(score),
+                score.games(),
+                name_width = name_width
+            )
+            .unwrap();
+            for &opponent in standings.iter() {
+                let head_to_head = self.head_to_head(engine, opponent);
+                if opponent == engine {
+                    table.push_str("      -");
+                } else if head_to_head.games() == 0 {
+                    table.push_str("       ");
+                } else {
+                    write!(table, "  {:>5.1}", points(head_to_head)).unwrap();
+                }
+            }
+        }
+        table
+    }
+}
+
+fn add_score(total: &mut MatchScore, score: MatchScore) {
+    total.wins += score.wins;
+    total.draws += score.draws;
+    total.losses += score.losses;
+}
+
+fn points(score: MatchScore) -> f64 {
+    score.wins as f64 + score.draws as f64 / 2.0
+}
@@ -0,0 +1,224 @@
+//! Texel-style tuning of evaluation parameters from game results.
+//!
+//! Each sample is a position together with the result of the game it was taken from. The evaluation is mapped to an expected score for white through a sigmoid, and the parameters are tuned by gradient descent to minimize the mean squared error between the expected scores and the actual results.
+//!
+//! The gradient is estimated by central differences, so any `TunableEval` implementation can be tuned, at the cost of two full error computations per parameter per step.
+
+#[cfg(feature = "rand")]
+pub mod spsa;
+
+use crate::{GameResult, TunableEval};
+#[cfg(feature = "rand")]
+use rand::seq::SliceRandom;
+#[cfg(feature = "rand")]
+use rand::Rng;
+
+/// A position, and the result of the game it was taken from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample<P> {
+    pub position: P,
+    pub result: GameResult,
+}
+
+/// Returns white's score for a game result: 1 for a white win, 0.5 for a draw and 0 for a black win.
+pub fn white_score(result: GameResult) -> f64 {
+    match result {
+        GameResult::WhiteWin => 1.0,
+        GameResult::BlackWin => 0.0,
+        GameResult::Draw => 0.5,
+    }
+}
+
+/// Maps an evaluation to white's expected score, using the scaling constant `k`.
+pub fn expected_score(eval: f32, k: f64) -> f64 {
+    1.0 / (1.0 + (-k * eval as f64).exp())
+}
+
+/// Returns the mean squared error between the expected and actual scores of the samples, using the parameters the positions currently hold.
+pub fn mean_squared_error<P: TunableEval>(samples: &[Sample<P>], k: f64) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples
+        .iter()
+        .map(|sample| {
+            let error =
+                white_score(sample.result) - expected_score(sample.position.static_eval(), k);
+            error * error
+        })
+        .sum::<f64>()
+        / samples.len() as f64
+}
+
+/// Sets the given parameters in every sample position.
+pub fn set_params<P: TunableEval>(samples: &mut [Sample<P>], params: &[f32]) {
+    for sample in samples.iter_mut() {
+        sample.position.set_params(params);
+    }
+}
+
+/// Interpolates a tapered parameter set between its opening and endgame values, for a game phase between 0.0 (opening) and 1.0 (endgame). See `GamePhase`.
+///
+/// Tapered evaluations typically tune the opening and endgame values as separate parameters, and call this when setting them in a position.
+///
+/// # Panics
+///
+/// Panics if the two parameter sets have different lengths.
+pub fn taper_params(opening: &[f32], endgame: &[f32], phase: f32) -> Vec<f32> {
+    assert_eq!(
+        opening.len(),
+        endgame.len(),
+        "Opening and endgame parameter sets must have the same length"
+    );
+    let phase = phase.clamp(0.0, 1.0);
+    opening
+        .iter()
+        .zip(endgame)
+        .map(|(opening, endgame)| opening * (1.0 - phase) + endgame * phase)
+        .collect()
+}
+
+/// Finds the scaling constant `k` that minimizes the mean squared error for the positions' current parameters.
+/// Searches between `min_k` and `max_k` with a golden-section search, so the error is assumed to have a single minimum in that range.
+pub fn fit_k<P: TunableEval>(samples: &[Sample<P>], min_k: f64, max_k: f64) -> f64 {
+    let inverse_phi = (5.0_f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (min_k, max_k);
+    while high - low > 1e-6 * (1.0 + high.abs()) {
+        let mid_low = high - inverse_phi * (high - low);
+        let mid_high = low + inverse_phi * (high - low);
+        if mean_squared_error(samples, mid_low) < mean_squared_error(samples, mid_high) {
+            high = mid_high;
+        } else {
+            low = mid_low;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// Shuffles the samples, and splits off `validation_fraction` of them as a validation set.
+/// Returns the training set and the validation set. Available with the `rand` feature.
+#[cfg(feature = "rand")]
+pub fn train_validation_split<P, R: Rng>(
+    mut samples: Vec<Sample<P>>,
+    validation_fraction: f64,
+    rng: &mut R,
+) -> (Vec<Sample<P>>, Vec<Sample<P>>) {
+    samples.shuffle(rng);
+    let validation_size = (samples.len() as f64 * validation_fraction.clamp(0.0, 1.0)) as usize;
+    let validation = samples.split_off(samples.len() - validation_size);
+    (samples, validation)
+}
+
+/// Settings for `tune`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuningOptions {
+    /// The scaling constant for the sigmoid. If None, it is fitted to the training set with the initial parameters.
+    pub k: Option<f64>,
+    pub learning_rate: f64,
+    /// The maximum number of gradient descent steps.
+    pub max_epochs: usize,
+    /// The step size for estimating the gradient by central differences.
+    pub epsilon: f32,
+    /// Stop early if the validation error has not improved for this many steps. Ignored if the validation set is empty.
+    pub patience: usize,
+}
+
+impl Default for TuningOptions {
+    fn default() -> Self {
+        TuningOptions {
+            k: None,
+            learning_rate: 1.0,
+            max_epochs: 100,
+            epsilon: 0.01,
+            patience: 5,
+        }
+    }
+}
+
+/// The outcome of a tuning run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuningResult {
+    /// The parameters with the lowest validation error, or the lowest training error if there is no validation set.
+    pub params: Vec<f32>,
+    pub k: f64,
+    /// The training and validation error after each step, starting with the errors of the initial parameters.
+    pub errors: Vec<(f64, f64)>,
+}
+
+/// Tunes the evaluation parameters by gradient descent, starting from the parameters held by the first training sample.
+///
+/// All sample positions hold the best parameters when the function returns.
+///
+/// # Panics
+///
+/// Panics if the training set is empty.
+pub fn tune<P: TunableEval>(
+    train: &mut [Sample<P>],
+    validation: &mut [Sample<P>],
+    options: &TuningOptions,
+) -> TuningResult {
+    let mut params = train
+        .first()
+        .expect("Cannot tune on an empty training set")
+        .position
+        .get_params();
+    set_params(train, &params);
+    set_params(validation, &params);
+    let k = options.k.unwrap_or_else(|| fit_k(train, 0.0, 10.0));
+
+    let errors_with = |train: &[Sample<P>], validation: &[Sample<P>]| {
+        (
+            mean_squared_error(train, k),
+            mean_squared_error(validation, k),
+        )
+    };
+    let mut errors = vec![errors_with(train, validation)];
+    let mut best_params = params.clone();
+    let mut best_error = errors[0];
+    let mut epochs_without_improvement = 0;
+
+    for _ in 0..options.max_epochs {
+        let mut gradient = vec![0.0; params.len()];
+        for i in 0..params.len() {
+            let original = params[i];
+            params[i] = original + options.epsilon;
+            set_params(train, &params);
+            let error_above = mean_squared_error(train, k);
+            params[i] = original - options.epsilon;
+            set_params(train, &params);
+            let error_below = mean_squared_error(train, k);
+            params[i] = original;
+            gradient[i] = (error_above - error_below) / (2.0 * options.epsilon as f64);
+        }
+        for (param, derivative) in params.iter_mut().zip(gradient) {
+            *param -= (options.learning_rate * derivative) as f32;
+        }
+        set_params(train, &params);
+        set_params(validation, &params);
+        let error = errors_with(train, validation);
+        errors.push(error);
+
+        let improved = if validation.is_empty() {
+            error.0 < best_error.0
+        } else {
+            error.1 < best_error.1
+        };
+        if improved {
+            best_params = params.clone();
+            best_error = error;
+            epochs_without_improvement = 0;
+        } else {
+            epochs_without_improvement += 1;
+            if !validation.is_empty() && epochs_without_improvement >= options.patience {
+                break;
+            }
+        }
+    }
+    set_params(train, &best_params);
+    set_params(validation, &best_params);
+    TuningResult {
+        params: best_params,
+        k,
+        errors,
+    }
+}
@@ -0,0 +1,233 @@
+//! Simultaneous perturbation stochastic approximation (SPSA) for tuning numeric parameters through matches.
+//!
+//! Each iteration perturbs every parameter randomly up or down at once, and plays a short match between the two perturbed parameter sets. The parameters are then moved towards the side that scored better. This only needs match results, so it can tune search parameters as well as evaluation parameters.
+//!
+//! The step sizes follow the conventions used by Fishtest: each parameter has a final perturbation size `c_end` and a final learning rate `r_end`.
+//! `arena_objective` plays the matches in the arena, given a function that builds an engine from parameter values.
+
+use crate::arena::{self, ArenaOptions};
+use crate::clock::TimeSource;
+use crate::stats::{self, MatchScore};
+use crate::{Engine, Position};
+use rand::Rng;
+
+/// A named parameter to be tuned.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpsaParameter {
+    pub name: String,
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+    /// The perturbation size at the final iteration.
+    pub c_end: f64,
+    /// The learning rate at the final iteration.
+    pub r_end: f64,
+}
+
+impl SpsaParameter {
+    /// Returns a parameter with a final perturbation size of a twentieth of its range, and a final learning rate of 0.002.
+    pub fn new(name: &str, value: f64, min: f64, max: f64) -> Self {
+        SpsaParameter {
+            name: name.to_string(),
+            value,
+            min,
+            max,
+            c_end: (max - min) / 20.0,
+            r_end: 0.002,
+        }
+    }
+}
+
+/// An SPSA tuning run over a fixed number of iterations.
+#[derive(Clone, Debug)]
+pub struct Spsa {
+    params: Vec<SpsaParameter>,
+    iterations: usize,
+    iteration: usize,
+    total_score: MatchScore,
+    /// The decay exponent for the learning rate.
+    pub alpha: f64,
+    /// The decay exponent for the perturbation size.
+    pub gamma: f64,
+    /// The stability constant for the learning rate, as a fraction of the total number of iterations.
+    pub a_ratio: f64,
+}
+
+impl Spsa {
+    /// Returns a tuning run for the given parameters, with the standard decay exponents 0.602 and 0.101.
+    pub fn new(params: Vec<SpsaParameter>, iterations: usize) -> Self {
+        Spsa {
+            params,
+            iterations,
+            iteration: 0,
+            total_score: MatchScore::default(),
+            alpha: 0.602,
+            gamma: 0.101,
+            a_ratio: 0.1,
+        }
+    }
+
+    /// Returns the parameters with their current values.
+    pub fn params(&self) -> &[SpsaParameter] {
+        &self.params
+    }
+
+    /// Returns the current parameter values, in the same order as `params`.
+    pub fn values(&self) -> Vec<f64> {
+        self.params.iter().map(|param| param.value).collect()
+    }
+
+    /// Returns the number of iterations that have been run.
+    pub fn iteration(&self) -> usize {
+        self.iteration
+    }
+
+    /// Returns the combined score of all matches so far, from the perspective of the positively perturbed parameters.
+    pub fn total_score(&self) -> MatchScore {
+        self.total_score
+    }
+
+    /// Returns the likelihood that the positively perturbed parameters are stronger, over all matches so far. See `stats::likelihood_of_superiority`.
+    ///
+    /// The perturbations are random, so this stays close to 0.5 for a well-behaved run. A value close to 0 or 1 suggests that the matches are biased, for example by an unbalanced opening book.
+    pub fn likelihood_of_superiority(&self) -> f64 {
+        stats::likelihood_of_superiority(self.total_score)
+    }
+
+    /// Returns true if all iterations have been run.
+    pub fn is_finished(&self) -> bool {
+        self.iteration >= self.iterations
+    }
+
+    /// Runs a single iteration.
+    ///
+    /// `play_match` is called with the positively and the negatively perturbed parameter values, in the same order as `params`. It must play a match between them, and return the result for the positively perturbed values.
+    pub fn step<R, F>(&mut self, rng: &mut R, mut play_match: F) -> MatchScore
+    where
+        R: Rng,
+        F: FnMut(&[f64], &[f64]) -> MatchScore,
+    {
+        let n = self.iterations as f64;
+        let k = self.iteration as f64;
+        let big_a = self.a_ratio * n;
+
+        let mut deltas = Vec::with_capacity(self.params.len());
+        let mut plus = Vec::with_capacity(self.params.len());
+        let mut minus = Vec::with_capacity(self.params.len());
+        for param in self.params.iter() {
+            let c_k = param.c_end * n.powf(self.gamma) / (k + 1.0).powf(self.gamma);
+            let delta = if rng.gen::<bool>() { 1.0 } else { -1.0 };
+            deltas.push((c_k, delta));
+            plus.push((param.value + c_k * delta).clamp(param.min, param.max));
+            minus.push((param.value - c_k * delta).clamp(param.min, param.max));
+        }
+
+        let score = play_match(&plus, &minus);
+        let result = score.wins as f64 - score.losses as f64;
+        for (param, (c_k, delta)) in self.params.iter_mut().zip(deltas) {
+            let a_end = param.r_end * param.c_end * param.c_end;
+            let a_k = a_end * (big_a + n).powf(self.alpha) / (big_a + k + 1.0).powf(self.alpha);
+            let r_k = a_k / (c_k * c_k);
+            param.value = (param.value + r_k * c_k * result * delta).clamp(param.min, param.max);
+        }
+
+        self.iteration += 1;
+        self.total_score.wins += score.wins;
+        self.total_score.draws += score.draws;
+        self.total_score.losses += score.losses;
+        score
+    }
+
+    /// Runs all remaining iterations, and returns the tuned parameter values.
+    pub fn run<R, F>(&mut self, rng: &mut R, mut play_match: F) -> Vec<f64>
+    where
+        R: Rng,
+        F: FnMut(&[f64], &[f64]) -> MatchScore,
+    {
+        while !self.is_finished() {
+            self.step(rng, &mut play_match);
+        }
+        self.values()
+    }
+}
+
+/// Returns a `play_match` function for `Spsa::step` and `Spsa::run`, which plays `num_games` games in the arena with `arena::play_match`.
+///
+/// `engine` is called twice per iteration, to build an engine with the positively and the negatively perturbed parameter values. The engines alternate colors, so `num_games` should be even.
+pub fn arena_objective<'a, P, E, F, T>(
+    mut engine: F,
+    start_position: &'a P,
+    openings: &'a [Vec<P::Move>],
+    num_games: usize,
+    options: &'a ArenaOptions,
+    time_source: &'a T,
+) -> impl FnMut(&[f64], &[f64]) -> MatchScore + 'a
+where
+    P: Position + Clone,
+    E: Engine<P>,
+    F: FnMut(&[f64]) -> E + 'a,
+    T: TimeSource + ?Sized,
+{
+    move |plus, minus| {
+        let mut plus_engine = engine(plus);
+        let mut minus_engine = engine(minus);
+        let games = arena::play_match(
+            &mut plus_engine,
+            &mut minus_engine,
+            start_position,
+            openings,
+            num_games,
+            options,
+            time_source,
+        );
+        arena::match_score(&games)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{ManualTimeSource, TimeControl};
+    use crate::testing::Nim;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use std::time::Duration;
+
+    /// Takes the same number of stones every move, or all of them if there are fewer left.
+    struct Take(u32);
+
+    impl Engine<Nim> for Take {
+        fn choose_move(&mut self, position: &Nim, _: Duration, _: Duration) -> u32 {
+            self.0.min(position.0)
+        }
+    }
+
+    #[test]
+    fn arena_objective_plays_matches() {
+        let options = ArenaOptions {
+            time_control: TimeControl::new(Duration::from_secs(10), Duration::ZERO),
+            max_plies: None,
+        };
+        let time_source = ManualTimeSource::default();
+        let start_position = Nim::start_position();
+        let mut objective = arena_objective(
+            |params: &[f64]| Take(params[0].round() as u32),
+            &start_position,
+            &[],
+            2,
+            &options,
+            &time_source,
+        );
+        // Both engines win with white from 10 stones, so each engine wins once
+        let score = objective(&[2.0], &[1.0]);
+        assert_eq!((score.wins, score.draws, score.losses), (1, 0, 1));
+
+        let mut spsa = Spsa::new(vec![SpsaParameter::new("take", 2.0, 1.0, 3.0)], 5);
+        let mut rng = SmallRng::seed_from_u64(0);
+        let values = spsa.run(&mut rng, objective);
+        assert_eq!(spsa.total_score().games(), 10);
+        assert!((1.0..=3.0).contains(&values[0]));
+        let los = spsa.likelihood_of_superiority();
+        assert!((0.0..=1.0).contains(&los));
+    }
+}
@@ -0,0 +1,112 @@
+//! The next version of the core `Position` trait, which can be adopted incrementally.
+//!
+//! `Position2` takes moves by reference in `move_is_legal`, and returns legal moves as an iterator of the game's choice, so that move generation can be lazy. Every `Position` implementation automatically implements `Position2` through a blanket implementation, so tools can be written against `Position2` and still accept existing games.
+//!
+//! New games can implement `Position2` directly, but can then only be used with the tools that have been ported to it. So far, that is the `perft` module.
+//!
+//! Since both traits have methods with the same names, import only one of them in a given module.
+
+use crate::{Color, GameResult, Position};
+use std::fmt;
+
+/// The next version of `Position`. See the module documentation.
+pub trait Position2: Sized {
+    /// The type for moves in the game.
+    type Move: Eq + Clone + fmt::Debug;
+    /// The type for a reverse move in the game.
+    type ReverseMove;
+    /// Optional Settings when initializing the position.
+    type Settings: Default;
+
+    /// Returns the starting position for the game. This function always produces identical values.
+    #[inline]
+    fn start_position() -> Self {
+        Self::start_position_with_settings(&Self::Settings::default())
+    }
+
+    /// Returns the starting position for the game with the given settings.
+    fn start_position_with_settings(settings: &Self::Settings) -> Self;
+
+    /// Returns the side to move for the current position.
+    fn side_to_move(&self) -> Color;
+
+    /// The iterator returned by `legal_moves`.
+    type MoveIter<'a>: Iterator<Item = Self::Move>
+    where
+        Self: 'a;
+
+    /// Returns an iterator over all legal moves for the side to move.
+    fn legal_moves(&self) -> Self::MoveIter<'_>;
+
+    /// Generates all legal moves for the side to move, and extends the provided data structure with them.
+    fn generate_moves<E: Extend<Self::Move>>(&self, moves: &mut E) {
+        moves.extend(self.legal_moves())
+    }
+
+    /// Checks if a move is legal in the current position.
+    fn move_is_legal(&self, mv: &Self::Move) -> bool {
+        self.legal_moves().any(|legal_move| legal_move == *mv)
+    }
+
+    /// Plays a move in the position. Also returns a ReverseMove to take the move back.
+    ///
+    /// Doing and then undoing a move always restores the position to exactly the same state.
+    fn do_move(&mut self, mv: Self::Move) -> Self::ReverseMove;
+
+    /// Reverse a move made by `do_move`.
+    ///
+    /// Doing and then undoing a move always restores the position to exactly the same state.
+    fn reverse_move(&mut self, mv: Self::ReverseMove);
+
+    /// Returns the result if the game is decided, otherwise returns None.
+    fn game_result(&self) -> Option<GameResult>;
+
+    /// The maximum number of plies in a game. See `Position::MAX_GAME_LENGTH`.
+    const MAX_GAME_LENGTH: Option<u32> = None;
+}
+
+impl<P: Position> Position2 for P {
+    type Move = P::Move;
+    type ReverseMove = P::ReverseMove;
+    type Settings = P::Settings;
+    type MoveIter<'a>
+        = std::vec::IntoIter<P::Move>
+    where
+        P: 'a;
+
+    fn start_position_with_settings(settings: &Self::Settings) -> Self {
+        <P as Position>::start_position_with_settings(settings)
+    }
+
+    fn side_to_move(&self) -> Color {
+        <P as Position>::side_to_move(self)
+    }
+
+    fn legal_moves(&self) -> Self::MoveIter<'_> {
+        let mut moves = vec![];
+        <P as Position>::generate_moves(self, &mut moves);
+        moves.into_iter()
+    }
+
+    fn generate_moves<E: Extend<Self::Move>>(&self, moves: &mut E) {
+        <P as Position>::generate_moves(self, moves)
+    }
+
+    fn move_is_legal(&self, mv: &Self::Move) -> bool {
+        <P as Position>::move_is_legal(self, mv.clone())
+    }
+
+    fn do_move(&mut self, mv: Self::Move) -> Self::ReverseMove {
+        <P as Position>::do_move(self, mv)
+    }
+
+    fn reverse_move(&mut self, mv: Self::ReverseMove) {
+        <P as Position>::reverse_move(self, mv)
+    }
+
+    fn game_result(&self) -> Option<GameResult> {
+        <P as Position>::game_result(self)
+    }
+
+    const MAX_GAME_LENGTH: Option<u32> = <P as Position>::MAX_GAME_LENGTH;
+}